@@ -0,0 +1,386 @@
+//! Sound effects and music. The game queues up Sounds as things
+//! happen, and the Audio plays them through whichever backend could
+//! be opened: a little synthesizer running in SDL's audio callback,
+//! or nothing at all, if there's no audio device to be had.
+use crate::settings::Settings;
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+
+const SAMPLE_RATE: i32 = 44100;
+/// How long notes take to fade in, in seconds, so they don't click.
+const NOTE_ATTACK: f32 = 0.005;
+/// How loud everything is before the volume settings, so that a few
+/// sounds playing at once don't clip.
+const MASTER_VOLUME: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sound {
+    Attack,
+    Hit,
+    Death,
+    Pickup,
+    Door,
+    Stairs,
+    Flame,
+    StopwatchTick,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Music {
+    Exploration,
+    Boss,
+    Victory,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Wave {
+    Square,
+    Triangle,
+    Noise,
+}
+
+/// One sweep of a waveform, a part of a sound effect.
+#[derive(Debug, Clone, Copy)]
+struct Tone {
+    wave: Wave,
+    /// When the tone starts after the sound does, in seconds.
+    delay: f32,
+    duration: f32,
+    /// The frequency is swept from the first to the second one, in Hz.
+    frequencies: (f32, f32),
+    volume: f32,
+}
+
+const fn tone(wave: Wave, delay: f32, duration: f32, from: f32, to: f32, volume: f32) -> Tone {
+    Tone {
+        wave,
+        delay,
+        duration,
+        frequencies: (from, to),
+        volume,
+    }
+}
+
+const ATTACK_TONES: &[Tone] = &[tone(Wave::Noise, 0.0, 0.08, 3000.0, 800.0, 0.5)];
+
+const HIT_TONES: &[Tone] = &[
+    tone(Wave::Square, 0.0, 0.1, 220.0, 80.0, 0.6),
+    tone(Wave::Noise, 0.0, 0.05, 1500.0, 500.0, 0.4),
+];
+
+const DEATH_TONES: &[Tone] = &[
+    tone(Wave::Square, 0.0, 0.15, 330.0, 220.0, 0.5),
+    tone(Wave::Square, 0.15, 0.3, 220.0, 55.0, 0.5),
+];
+
+const PICKUP_TONES: &[Tone] = &[
+    tone(Wave::Triangle, 0.0, 0.06, 660.0, 660.0, 0.7),
+    tone(Wave::Triangle, 0.06, 0.1, 990.0, 990.0, 0.7),
+];
+
+const DOOR_TONES: &[Tone] = &[
+    tone(Wave::Noise, 0.0, 0.25, 300.0, 150.0, 0.4),
+    tone(Wave::Triangle, 0.0, 0.25, 110.0, 90.0, 0.6),
+];
+
+const STAIRS_TONES: &[Tone] = &[
+    tone(Wave::Triangle, 0.0, 0.1, 392.0, 392.0, 0.6),
+    tone(Wave::Triangle, 0.1, 0.1, 330.0, 330.0, 0.6),
+    tone(Wave::Triangle, 0.2, 0.1, 262.0, 262.0, 0.6),
+    tone(Wave::Triangle, 0.3, 0.25, 196.0, 196.0, 0.6),
+];
+
+const FLAME_TONES: &[Tone] = &[tone(Wave::Noise, 0.0, 0.4, 600.0, 200.0, 0.4)];
+
+const STOPWATCH_TICK_TONES: &[Tone] = &[tone(Wave::Square, 0.0, 0.02, 1800.0, 1800.0, 0.3)];
+
+impl Sound {
+    fn tones(self) -> &'static [Tone] {
+        match self {
+            Sound::Attack => ATTACK_TONES,
+            Sound::Hit => HIT_TONES,
+            Sound::Death => DEATH_TONES,
+            Sound::Pickup => PICKUP_TONES,
+            Sound::Door => DOOR_TONES,
+            Sound::Stairs => STAIRS_TONES,
+            Sound::Flame => FLAME_TONES,
+            Sound::StopwatchTick => STOPWATCH_TICK_TONES,
+        }
+    }
+}
+
+/// A looping melody, played one note per beat.
+struct Track {
+    wave: Wave,
+    /// The length of a beat, in seconds.
+    beat: f32,
+    /// MIDI note numbers, 0 for a rest.
+    notes: &'static [u8],
+    volume: f32,
+}
+
+impl Music {
+    fn track(self) -> Track {
+        match self {
+            Music::Exploration => Track {
+                wave: Wave::Triangle,
+                beat: 0.4,
+                notes: &[
+                    57, 60, 64, 60, 57, 0, 52, 0, //
+                    55, 59, 62, 59, 55, 0, 50, 0, //
+                    53, 57, 60, 57, 53, 0, 52, 56, //
+                    57, 0, 0, 0, 45, 0, 0, 0,
+                ],
+                volume: 0.5,
+            },
+            Music::Boss => Track {
+                wave: Wave::Square,
+                beat: 0.15,
+                notes: &[
+                    40, 40, 52, 40, 40, 51, 40, 50, //
+                    40, 40, 52, 40, 43, 0, 42, 0, //
+                    38, 38, 50, 38, 38, 49, 38, 48, //
+                    38, 38, 50, 38, 41, 0, 40, 0,
+                ],
+                volume: 0.3,
+            },
+            Music::Victory => Track {
+                wave: Wave::Square,
+                beat: 0.2,
+                notes: &[
+                    60, 64, 67, 72, 0, 67, 72, 0, //
+                    65, 69, 72, 77, 0, 72, 76, 0, //
+                    0, 0, 0, 0, 0, 0, 0, 0,
+                ],
+                volume: 0.3,
+            },
+        }
+    }
+}
+
+fn note_frequency(note: u8) -> f32 {
+    440.0 * 2f32.powf((note as f32 - 69.0) / 12.0)
+}
+
+/// The things that can play the game's audio.
+pub trait AudioBackend {
+    fn play(&mut self, sound: Sound);
+    /// Switches the looping music over to `music`, or stops it.
+    fn set_music(&mut self, music: Option<Music>);
+    /// Sets the volumes of the sound effects and the music, 0.0 - 1.0.
+    fn set_volumes(&mut self, sound: f32, music: f32);
+}
+
+/// Plays nothing. Used when there's no audio device to play on.
+pub struct NullBackend;
+
+impl AudioBackend for NullBackend {
+    fn play(&mut self, _sound: Sound) {}
+    fn set_music(&mut self, _music: Option<Music>) {}
+    fn set_volumes(&mut self, _sound: f32, _music: f32) {}
+}
+
+/// Plays through SDL, with the Synth generating the samples in the
+/// audio callback.
+pub struct SdlBackend {
+    device: AudioDevice<Synth>,
+}
+
+impl SdlBackend {
+    pub fn open(sdl: &sdl2::Sdl) -> Result<SdlBackend, String> {
+        let audio = sdl.audio()?;
+        let spec = AudioSpecDesired {
+            freq: Some(SAMPLE_RATE),
+            channels: Some(1),
+            samples: Some(1024),
+        };
+        let device = audio.open_playback(None, &spec, |spec| Synth::new(spec.freq as f32))?;
+        device.resume();
+        Ok(SdlBackend { device })
+    }
+}
+
+impl AudioBackend for SdlBackend {
+    fn play(&mut self, sound: Sound) {
+        let mut synth = self.device.lock();
+        for &tone in sound.tones() {
+            synth.voices.push(Voice::new(tone));
+        }
+    }
+
+    fn set_music(&mut self, music: Option<Music>) {
+        let mut synth = self.device.lock();
+        synth.music = music.map(|music| (music.track(), 0.0));
+    }
+
+    fn set_volumes(&mut self, sound: f32, music: f32) {
+        let mut synth = self.device.lock();
+        synth.sound_volume = sound;
+        synth.music_volume = music;
+    }
+}
+
+/// A tone being played.
+struct Voice {
+    tone: Tone,
+    time: f32,
+    phase: f32,
+}
+
+impl Voice {
+    fn new(tone: Tone) -> Voice {
+        Voice {
+            tone,
+            time: -tone.delay,
+            phase: 0.0,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.time >= self.tone.duration
+    }
+}
+
+struct Synth {
+    sample_rate: f32,
+    voices: Vec<Voice>,
+    /// The track playing, and how far into it, in seconds.
+    music: Option<(Track, f32)>,
+    music_phase: f32,
+    sound_volume: f32,
+    music_volume: f32,
+    /// The state of the random number generator for the noise.
+    noise: u32,
+}
+
+impl Synth {
+    fn new(sample_rate: f32) -> Synth {
+        Synth {
+            sample_rate,
+            voices: Vec::new(),
+            music: None,
+            music_phase: 0.0,
+            sound_volume: 1.0,
+            music_volume: 1.0,
+            noise: 0x1234_5678,
+        }
+    }
+
+    /// Returns a sample of the waveform at `phase` (0.0 - 1.0).
+    fn sample(&mut self, wave: Wave, phase: f32) -> f32 {
+        match wave {
+            Wave::Square if phase < 0.5 => 1.0,
+            Wave::Square => -1.0,
+            Wave::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Wave::Noise => {
+                // Xorshift, only advanced once per cycle so that the
+                // frequency affects the pitch of the noise
+                if phase < 1.0 / 32.0 {
+                    self.noise ^= self.noise << 13;
+                    self.noise ^= self.noise >> 17;
+                    self.noise ^= self.noise << 5;
+                }
+                (self.noise as f32 / u32::MAX as f32) * 2.0 - 1.0
+            }
+        }
+    }
+
+    fn next_sound_sample(&mut self, dt: f32) -> f32 {
+        let mut output = 0.0;
+        for i in 0..self.voices.len() {
+            let (tone, time, phase) = {
+                let voice = &mut self.voices[i];
+                voice.time += dt;
+                if voice.time < 0.0 {
+                    continue;
+                }
+                let progress = voice.time / voice.tone.duration;
+                let (from, to) = voice.tone.frequencies;
+                let frequency = from + (to - from) * progress.min(1.0);
+                voice.phase = (voice.phase + frequency * dt).fract();
+                (voice.tone, voice.time, voice.phase)
+            };
+            let envelope = (time / NOTE_ATTACK).min(1.0) * (1.0 - time / tone.duration).max(0.0);
+            output += self.sample(tone.wave, phase) * tone.volume * envelope;
+        }
+        self.voices.retain(|voice| !voice.is_finished());
+        output
+    }
+
+    fn next_music_sample(&mut self, dt: f32) -> f32 {
+        let (wave, frequency, envelope, volume) = match &mut self.music {
+            Some((track, time)) => {
+                let length = track.beat * track.notes.len() as f32;
+                *time = (*time + dt) % length;
+                let beat = (*time / track.beat) as usize % track.notes.len();
+                let beat_time = *time - beat as f32 * track.beat;
+                let note = track.notes[beat];
+                if note == 0 {
+                    return 0.0;
+                }
+                let envelope = (beat_time / NOTE_ATTACK).min(1.0) * (1.0 - beat_time / track.beat);
+                (track.wave, note_frequency(note), envelope, track.volume)
+            }
+            None => return 0.0,
+        };
+        self.music_phase = (self.music_phase + frequency * dt).fract();
+        self.sample(wave, self.music_phase) * envelope * volume
+    }
+}
+
+impl AudioCallback for Synth {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        let dt = 1.0 / self.sample_rate;
+        for x in out.iter_mut() {
+            let sound = self.next_sound_sample(dt) * self.sound_volume;
+            let music = self.next_music_sample(dt) * self.music_volume;
+            *x = ((sound + music) * MASTER_VOLUME).clamp(-1.0, 1.0);
+        }
+    }
+}
+
+pub struct Audio {
+    backend: Box<dyn AudioBackend>,
+    music: Option<Music>,
+}
+
+impl Audio {
+    /// Opens the audio device, falling back to not playing anything
+    /// if that fails.
+    pub fn new(sdl: &sdl2::Sdl, settings: &Settings) -> Audio {
+        let backend: Box<dyn AudioBackend> = match SdlBackend::open(sdl) {
+            Ok(backend) => Box::new(backend),
+            Err(err) => {
+                log::warn!(
+                    "Could not open the audio device, playing without sound: {}",
+                    err
+                );
+                Box::new(NullBackend)
+            }
+        };
+        let mut audio = Audio {
+            backend,
+            music: None,
+        };
+        audio.apply_settings(settings);
+        audio
+    }
+
+    pub fn play(&mut self, sound: Sound) {
+        self.backend.play(sound);
+    }
+
+    /// Switches the music, if it isn't already playing.
+    pub fn set_music(&mut self, music: Option<Music>) {
+        if self.music != music {
+            self.music = music;
+            self.backend.set_music(music);
+        }
+    }
+
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.backend
+            .set_volumes(settings.sound_volume, settings.music_volume);
+    }
+}
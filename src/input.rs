@@ -3,14 +3,39 @@
 
 use sdl2::keyboard::Keycode;
 
-const KEYS_MOVE_UP: [Keycode; 3] = [Keycode::Up, Keycode::W, Keycode::K];
-const KEYS_MOVE_DOWN: [Keycode; 3] = [Keycode::Down, Keycode::S, Keycode::J];
-const KEYS_MOVE_LEFT: [Keycode; 3] = [Keycode::Left, Keycode::A, Keycode::H];
-const KEYS_MOVE_RIGHT: [Keycode; 3] = [Keycode::Right, Keycode::D, Keycode::L];
+const KEYS_MOVE_UP: [Keycode; 4] = [Keycode::Up, Keycode::W, Keycode::K, Keycode::Kp8];
+const KEYS_MOVE_DOWN: [Keycode; 4] = [Keycode::Down, Keycode::S, Keycode::J, Keycode::Kp2];
+const KEYS_MOVE_LEFT: [Keycode; 4] = [Keycode::Left, Keycode::A, Keycode::H, Keycode::Kp4];
+const KEYS_MOVE_RIGHT: [Keycode; 4] = [Keycode::Right, Keycode::D, Keycode::L, Keycode::Kp6];
+const KEYS_MOVE_UP_LEFT: [Keycode; 2] = [Keycode::Y, Keycode::Kp7];
+const KEYS_MOVE_UP_RIGHT: [Keycode; 2] = [Keycode::U, Keycode::Kp9];
+const KEYS_MOVE_DOWN_LEFT: [Keycode; 2] = [Keycode::B, Keycode::Kp1];
+const KEYS_MOVE_DOWN_RIGHT: [Keycode; 2] = [Keycode::N, Keycode::Kp3];
 const KEYS_PICKUP: [Keycode; 1] = [Keycode::Comma];
 const KEYS_WAIT: [Keycode; 2] = [Keycode::Space, Keycode::Period];
 const KEYS_NEXT_LEVEL: [Keycode; 1] = [Keycode::Return];
 const KEYS_RESTART: [Keycode; 1] = [Keycode::R];
+const KEYS_INVENTORY: [Keycode; 2] = [Keycode::I, Keycode::Tab];
+const KEYS_DROP: [Keycode; 3] = [Keycode::X, Keycode::Delete, Keycode::Backspace];
+const KEYS_CONFIRM: [Keycode; 2] = [Keycode::Return, Keycode::KpEnter];
+const KEYS_CANCEL: [Keycode; 1] = [Keycode::Escape];
+const KEYS_THROW: [Keycode; 2] = [Keycode::T, Keycode::F];
+const KEYS_BASH: [Keycode; 1] = [Keycode::V];
+const KEYS_MAP: [Keycode; 1] = [Keycode::M];
+const KEYS_LOOK: [Keycode; 2] = [Keycode::E, Keycode::Semicolon];
+const KEYS_ZOOM_IN: [Keycode; 3] = [Keycode::Plus, Keycode::Equals, Keycode::KpPlus];
+const KEYS_ZOOM_OUT: [Keycode; 2] = [Keycode::Minus, Keycode::KpMinus];
+const KEYS_SLOTS: [Keycode; 9] = [
+    Keycode::Num1,
+    Keycode::Num2,
+    Keycode::Num3,
+    Keycode::Num4,
+    Keycode::Num5,
+    Keycode::Num6,
+    Keycode::Num7,
+    Keycode::Num8,
+    Keycode::Num9,
+];
 
 pub fn is_key_move_up(keycode: Keycode) -> bool {
     KEYS_MOVE_UP.contains(&keycode)
@@ -28,6 +53,22 @@ pub fn is_key_move_right(keycode: Keycode) -> bool {
     KEYS_MOVE_RIGHT.contains(&keycode)
 }
 
+pub fn is_key_move_up_left(keycode: Keycode) -> bool {
+    KEYS_MOVE_UP_LEFT.contains(&keycode)
+}
+
+pub fn is_key_move_up_right(keycode: Keycode) -> bool {
+    KEYS_MOVE_UP_RIGHT.contains(&keycode)
+}
+
+pub fn is_key_move_down_left(keycode: Keycode) -> bool {
+    KEYS_MOVE_DOWN_LEFT.contains(&keycode)
+}
+
+pub fn is_key_move_down_right(keycode: Keycode) -> bool {
+    KEYS_MOVE_DOWN_RIGHT.contains(&keycode)
+}
+
 pub fn is_key_pickup(keycode: Keycode) -> bool {
     KEYS_PICKUP.contains(&keycode)
 }
@@ -43,3 +84,75 @@ pub fn is_key_next_level(keycode: Keycode) -> bool {
 pub fn is_key_restart(keycode: Keycode) -> bool {
     KEYS_RESTART.contains(&keycode)
 }
+
+pub fn is_key_inventory(keycode: Keycode) -> bool {
+    KEYS_INVENTORY.contains(&keycode)
+}
+
+pub fn is_key_drop(keycode: Keycode) -> bool {
+    KEYS_DROP.contains(&keycode)
+}
+
+pub fn is_key_confirm(keycode: Keycode) -> bool {
+    KEYS_CONFIRM.contains(&keycode)
+}
+
+pub fn is_key_cancel(keycode: Keycode) -> bool {
+    KEYS_CANCEL.contains(&keycode)
+}
+
+pub fn is_key_throw(keycode: Keycode) -> bool {
+    KEYS_THROW.contains(&keycode)
+}
+
+pub fn is_key_bash(keycode: Keycode) -> bool {
+    KEYS_BASH.contains(&keycode)
+}
+
+pub fn is_key_map(keycode: Keycode) -> bool {
+    KEYS_MAP.contains(&keycode)
+}
+
+pub fn is_key_look(keycode: Keycode) -> bool {
+    KEYS_LOOK.contains(&keycode)
+}
+
+pub fn is_key_zoom_in(keycode: Keycode) -> bool {
+    KEYS_ZOOM_IN.contains(&keycode)
+}
+
+pub fn is_key_zoom_out(keycode: Keycode) -> bool {
+    KEYS_ZOOM_OUT.contains(&keycode)
+}
+
+/// Returns the direction the key moves in, if it's a movement key.
+/// The diagonal keys only count if `diagonal` is set.
+pub fn get_key_direction(keycode: Keycode, diagonal: bool) -> Option<(i32, i32)> {
+    if is_key_move_up(keycode) {
+        Some((0, -1))
+    } else if is_key_move_down(keycode) {
+        Some((0, 1))
+    } else if is_key_move_left(keycode) {
+        Some((-1, 0))
+    } else if is_key_move_right(keycode) {
+        Some((1, 0))
+    } else if !diagonal {
+        None
+    } else if is_key_move_up_left(keycode) {
+        Some((-1, -1))
+    } else if is_key_move_up_right(keycode) {
+        Some((1, -1))
+    } else if is_key_move_down_left(keycode) {
+        Some((-1, 1))
+    } else if is_key_move_down_right(keycode) {
+        Some((1, 1))
+    } else {
+        None
+    }
+}
+
+/// Returns the (zero-based) slot the key refers to, if it's a number
+/// key.
+pub fn get_key_slot(keycode: Keycode) -> Option<usize> {
+    KEYS_SLOTS.iter().position(|key| *key == keycode)
+}
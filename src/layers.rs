@@ -1,8 +1,10 @@
 pub const DEAD: f32 = 0.1;
 pub const ALIVE: f32 = 0.2;
+pub const CURSOR: f32 = 0.3;
 pub const HEARTS: f32 = 0.4;
 pub const UI_BG: f32 = 0.5;
+pub const UI_MAP: f32 = 0.52;
+pub const UI_MAP_FLOOR: f32 = 0.53;
+pub const UI_MAP_MARKERS: f32 = 0.54;
 pub const UI_TEXT: f32 = 0.7;
-
-#[allow(dead_code)]
 pub const UI_BUTTONS: f32 = 0.6;
@@ -2,19 +2,26 @@
 
 use fae::{Context, Font, GraphicsContext, Image, SpritesheetBuilder};
 use sdl2::event::{Event, WindowEvent};
+use sdl2::mouse::MouseButton;
+use sdl2::video::{FullscreenType, SwapInterval, Window};
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
+mod audio;
 mod error;
 mod input;
 mod layers;
+mod settings;
 mod sprites;
 mod ui;
 mod world;
 
+use audio::{Audio, Music};
 use error::Logger;
-use ui::Ui;
-use world::{PlayerAction, World};
+use settings::Settings;
+use ui::screens::{Screen, ScreenCommand, Screens};
+use ui::{Ui, UiMode};
+use world::{GameOptions, PlayerAction, World};
 
 static LOGGER: Logger = Logger;
 pub static TITLE: &str = "The Dragon's Dungeon";
@@ -24,6 +31,29 @@ fn main() -> Result<(), fae::Error> {
         .map(|_| log::set_max_level(log::LevelFilter::Info))
         .ok(); // set_logger will only fail if a logger has already been set
 
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(i) = args.iter().position(|arg| arg == "--loot-distribution") {
+        let seeds = args
+            .get(i + 1)
+            .and_then(|s| world::loot::parse_seed_range(s));
+        if let Some(seeds) = seeds {
+            world::loot::print_distribution(seeds);
+        } else {
+            eprintln!("Usage: --loot-distribution <first seed>..<last seed>");
+        }
+        return Ok(());
+    }
+
+    // The options given on the command line are turned on on top of
+    // the ones in the settings file, but they're kept apart from the
+    // settings so that they don't get saved.
+    let mut settings = Settings::load();
+    let cli_options = GameOptions {
+        combat_rolls: args.iter().any(|arg| arg == "--combat-rolls"),
+        identification: args.iter().any(|arg| arg == "--identification"),
+        diagonal_movement: args.iter().any(|arg| arg == "--diagonal"),
+    };
+
     let sdl = match sdl2::init() {
         Ok(sdl) => sdl,
         Err(err) => {
@@ -42,8 +72,11 @@ fn main() -> Result<(), fae::Error> {
         }
     };
 
-    let window = sdl_video
-        .window(TITLE, 800, 600)
+    let mut audio = Audio::new(&sdl, &settings);
+
+    let (window_width, window_height) = settings.window_size;
+    let mut window = sdl_video
+        .window(TITLE, window_width, window_height)
         .opengl()
         .allow_highdpi()
         .resizable()
@@ -52,6 +85,7 @@ fn main() -> Result<(), fae::Error> {
 
     let gl_context = window.gl_create_context().unwrap();
     fae::gl::load_with(|name| sdl_video.gl_get_proc_address(name) as *const _);
+    apply_settings(&mut window, &sdl_video, &settings, false);
 
     let mut fae_ctx: Context = Context::new();
     let ttf_plain = include_bytes!("../fonts/world-of-fonts/magic-forest.ttf").to_vec();
@@ -69,8 +103,9 @@ fn main() -> Result<(), fae::Error> {
         .alpha_blending(true, true)
         .build(&mut fae_ctx);
 
-    let mut world = World::new();
+    let mut world: Option<World> = None;
     let mut ui = Ui::new();
+    let mut screens = Screens::new();
     let mut show_debug_info = cfg!(debug_assertions);
 
     let mut event_pump = sdl.event_pump().unwrap();
@@ -81,24 +116,68 @@ fn main() -> Result<(), fae::Error> {
     let mut victory_override = false;
 
     'game_loop: loop {
-        let game_over = !world.player().is_alive() || game_over_override;
-        let victory = world.is_dragon_dead() || victory_override;
+        let game_over = world.iter().any(|w| !w.player().is_alive()) || game_over_override;
+        let victory = world.iter().any(|w| w.is_dragon_dead()) || victory_override;
+        let game_in_progress = world.is_some() && !game_over && !victory;
 
         for event in event_pump.poll_iter() {
             match event {
                 Event::Quit { .. } => {
                     break 'game_loop;
                 }
-                Event::Window { win_event, .. } => match win_event {
-                    WindowEvent::Resized(_, _) => unsafe {
-                        let (width, height) = window.drawable_size();
-                        fae::gl::Viewport(0, 0, width as i32, height as i32);
-                    },
-                    _ => {}
-                },
+                Event::Window {
+                    win_event:
+                        WindowEvent::Resized(width, height) | WindowEvent::SizeChanged(width, height),
+                    ..
+                } => {
+                    if !settings.fullscreen {
+                        settings.window_size = (width as u32, height as u32);
+                    }
+                    let (width, height) = window.drawable_size();
+                    unsafe { fae::gl::Viewport(0, 0, width as i32, height as i32) };
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } if !screens.is_playing() => {
+                    match screens.input(keycode, &mut settings, game_in_progress) {
+                        Some(ScreenCommand::NewGame) => {
+                            let options = settings.options.or(&cli_options);
+                            let mut new_world = World::new(options);
+                            new_world.set_tile_size(settings.tile_size);
+                            world = Some(new_world);
+                            ui = Ui::new();
+                            action_queue.clear();
+                            game_over_override = false;
+                            victory_override = false;
+                            screens.start_game();
+                        }
+                        Some(ScreenCommand::ApplySettings) => {
+                            apply_settings(&mut window, &sdl_video, &settings, true);
+                            audio.apply_settings(&settings);
+                            if let Some(world) = &mut world {
+                                world.set_options(&settings.options.or(&cli_options));
+                                world.set_tile_size(settings.tile_size);
+                            }
+                            settings.save();
+                        }
+                        Some(ScreenCommand::Quit) => break 'game_loop,
+                        None => {}
+                    }
+                }
                 Event::KeyDown { keycode, .. } => {
-                    if let Some(keycode) = keycode {
-                        if input::is_key_move_up(keycode) {
+                    if let (Some(keycode), Some(world)) = (keycode, &mut world) {
+                        let diagonal = world.options().diagonal_movement;
+                        if settings.sequenced_animations {
+                            world.skip_animation();
+                        }
+                        if ui.is_capturing_input() {
+                            if let Some(action) = ui.input(keycode, world) {
+                                action_queue.push_back(action);
+                            }
+                        } else if input::is_key_cancel(keycode) {
+                            screens.pause();
+                        } else if input::is_key_move_up(keycode) {
                             action_queue.push_back(PlayerAction::MoveUp);
                         } else if input::is_key_move_down(keycode) {
                             action_queue.push_back(PlayerAction::MoveDown);
@@ -106,15 +185,44 @@ fn main() -> Result<(), fae::Error> {
                             action_queue.push_back(PlayerAction::MoveRight);
                         } else if input::is_key_move_left(keycode) {
                             action_queue.push_back(PlayerAction::MoveLeft);
+                        } else if diagonal && input::is_key_move_up_left(keycode) {
+                            action_queue.push_back(PlayerAction::MoveUpLeft);
+                        } else if diagonal && input::is_key_move_up_right(keycode) {
+                            action_queue.push_back(PlayerAction::MoveUpRight);
+                        } else if diagonal && input::is_key_move_down_left(keycode) {
+                            action_queue.push_back(PlayerAction::MoveDownLeft);
+                        } else if diagonal && input::is_key_move_down_right(keycode) {
+                            action_queue.push_back(PlayerAction::MoveDownRight);
                         } else if input::is_key_pickup(keycode) {
-                            action_queue.push_back(PlayerAction::Pickup);
+                            if world.ware_under_player().is_some() {
+                                action_queue.push_back(PlayerAction::Buy);
+                            } else if world.pickup_needs_free_slot() {
+                                ui.mode = UiMode::ReplaceItem(0);
+                            } else {
+                                action_queue.push_back(PlayerAction::Pickup);
+                            }
+                        } else if input::is_key_inventory(keycode) {
+                            ui.mode = UiMode::Inventory(0);
+                        } else if let Some(slot) = input::get_key_slot(keycode) {
+                            if let Some(action) = ui.use_belt_slot(slot, world) {
+                                action_queue.push_back(action);
+                            }
+                        } else if input::is_key_throw(keycode) {
+                            ui.throw_from_belt(world);
+                        } else if input::is_key_bash(keycode) {
+                            ui.start_shield_bash(world);
+                        } else if input::is_key_map(keycode) {
+                            ui.mode = UiMode::Map;
+                        } else if input::is_key_look(keycode) {
+                            ui.start_looking(world);
+                        } else if input::is_key_zoom_in(keycode) {
+                            zoom(&mut settings, world, &window, true);
+                        } else if input::is_key_zoom_out(keycode) {
+                            zoom(&mut settings, world, &window, false);
                         } else if input::is_key_wait(keycode) {
                             action_queue.push_back(PlayerAction::Wait);
                         } else if input::is_key_next_level(keycode) {
                             action_queue.push_back(PlayerAction::NextLevel);
-                        } else if input::is_key_restart(keycode) && (game_over || victory) {
-                            world = World::new();
-                            action_queue.clear();
                         } else {
                             // Debug keys, not part of the input system:
 
@@ -123,13 +231,13 @@ fn main() -> Result<(), fae::Error> {
                                 show_debug_info = !show_debug_info;
                             }
                             if show_debug_info {
-                                if keycode == Keycode::Num1 {
+                                if keycode == Keycode::F6 {
                                     world.spawn(world::entities::PROTO_SKELETON.clone_at(5, 5));
                                 }
-                                if keycode == Keycode::Num2 {
+                                if keycode == Keycode::F7 {
                                     world.spawn(world::entities::PROTO_ZOMBIE.clone_at(5, 5));
                                 }
-                                if keycode == Keycode::Num3 {
+                                if keycode == Keycode::F8 {
                                     world.spawn(world::entities::PROTO_DRAGON.clone_at(5, 5));
                                 }
                                 if keycode == Keycode::F4 {
@@ -142,44 +250,84 @@ fn main() -> Result<(), fae::Error> {
                         }
                     }
                 }
+                Event::MouseMotion { x, y, .. } => {
+                    let position = mouse_position_on_map(&window, x, y);
+                    if let (true, Some(world), Some((x, y))) =
+                        (screens.is_playing(), &world, position)
+                    {
+                        let (width, height, _) = view_size(&window);
+                        ui.mouse_moved(x, y, width, height, world);
+                    }
+                }
+                Event::MouseWheel { y, .. } if y != 0 => {
+                    if let (true, Some(world)) = (screens.is_playing(), &mut world) {
+                        zoom(&mut settings, world, &window, y > 0);
+                    }
+                }
+                Event::MouseButtonDown {
+                    mouse_btn, x, y, ..
+                } => {
+                    if !screens.is_playing() || mouse_position_on_map(&window, x, y).is_none() {
+                        continue;
+                    }
+                    if let Some(world) = &world {
+                        let left_button = mouse_btn == MouseButton::Left;
+                        if let Some(action) = ui.mouse_clicked(left_button, world) {
+                            action_queue.push_back(action);
+                        }
+                    }
+                }
                 _ => {}
             }
         }
 
-        // One action per frame:
-        if let Some(action) = action_queue.pop_front() {
-            // TODO: Split updates into "player updates" and "world updates"
-            // where input is disabled while the player
-            // animates, and the player gets to see what the enemies
-            // do in response. This should be an option.
-            world.update(action, show_debug_info);
+        if let Some(world) = &mut world {
+            // One action per frame:
+            if let (true, Some(action)) = (screens.is_playing(), action_queue.pop_front()) {
+                world.update(action, show_debug_info);
+            }
+            ui.update(world);
         }
+        screens.update(game_over, victory);
 
-        let (width, height) = (window.size().0 as f32, window.size().1 as f32);
-        let physical_width = window.drawable_size().0 as f32;
-        let dpi_factor = physical_width / width;
+        let (width, height, dpi_factor) = view_size(&window);
 
         let current_time = Instant::now();
         if let Some(last_frame_time) = last_frame_time {
             let duration: Duration = current_time - last_frame_time;
             let seconds = duration.as_secs_f32();
-            world.animate(seconds, 0.2);
+            if let Some(world) = &mut world {
+                world.animate(
+                    seconds,
+                    settings.round_duration,
+                    settings.sequenced_animations,
+                );
+                world.move_camera(seconds, settings.smooth_camera);
+            }
         }
         last_frame_time = Some(current_time);
 
+        if let Some(world) = &mut world {
+            for sound in world.take_sounds() {
+                audio.play(sound);
+            }
+        }
+        let music = match screens.top() {
+            Screen::Victory => Some(Music::Victory),
+            Screen::GameOver => None,
+            _ if world.iter().any(|w| w.is_facing_dragon()) => Some(Music::Boss),
+            _ => Some(Music::Exploration),
+        };
+        audio.set_music(music);
+
         fae::profiler::refresh();
         let mut ctx: GraphicsContext = fae_ctx.start_frame(width, height, dpi_factor);
 
-        world.render(&mut ctx, &font, &tileset, show_debug_info);
-        ui.render(
-            &mut ctx,
-            &font,
-            &ui_tileset,
-            &world,
-            game_over,
-            victory,
-            show_debug_info,
-        );
+        if let (true, Some(world)) = (screens.is_in_game(), &world) {
+            world.render(&mut ctx, &font, &tileset, show_debug_info);
+            ui.render(&mut ctx, &font, &ui_tileset, world, show_debug_info);
+        }
+        screens.render(&mut ctx, &font, &ui_tileset, &settings, game_in_progress);
 
         ctx.finish_frame();
         fae_ctx.render(width, height, (0.1, 0.1, 0.1, 1.0));
@@ -187,6 +335,79 @@ fn main() -> Result<(), fae::Error> {
         fae_ctx.synchronize();
     }
 
+    settings.save();
     drop(gl_context);
     Ok(())
 }
+
+/// Returns the size of the area the frames are drawn in, and how many
+/// physical pixels there are per unit of it.
+fn view_size(window: &Window) -> (f32, f32, f32) {
+    let (physical_width, physical_height) = window.drawable_size();
+    let dpi_factor = physical_width as f32 / window.size().0.max(1) as f32;
+    (
+        physical_width as f32 / dpi_factor,
+        physical_height as f32 / dpi_factor,
+        dpi_factor,
+    )
+}
+
+/// Zooms the map in or out, no further in than the size the player's
+/// room still fits the map area with.
+fn zoom(settings: &mut Settings, world: &mut World, window: &Window, zoom_in: bool) {
+    let (width, height, _) = view_size(window);
+    let fitting_tile_size = world.fitting_tile_size(width - ui::UI_AREA_WIDTH, height);
+    settings.zoom(zoom_in, fitting_tile_size);
+    world.set_tile_size(settings.tile_size);
+}
+
+/// Converts the mouse position from window coordinates into the
+/// coordinates the frames are drawn in, going through the drawable
+/// size. Returns None if the mouse is over the side panel instead of
+/// the map.
+fn mouse_position_on_map(window: &Window, x: i32, y: i32) -> Option<(f32, f32)> {
+    let (window_width, window_height) = window.size();
+    let (physical_width, physical_height) = window.drawable_size();
+    let (width, _, dpi_factor) = view_size(window);
+    let physical_x = x as f32 * physical_width as f32 / window_width.max(1) as f32;
+    let physical_y = y as f32 * physical_height as f32 / window_height.max(1) as f32;
+    let (x, y) = (physical_x / dpi_factor, physical_y / dpi_factor);
+    if x < width - ui::UI_AREA_WIDTH {
+        Some((x, y))
+    } else {
+        None
+    }
+}
+
+/// Applies the display settings to the window. The window is only
+/// resized if `resize` is set, so that a window size chosen by
+/// dragging the window edges isn't overridden.
+fn apply_settings(
+    window: &mut Window,
+    video: &sdl2::VideoSubsystem,
+    settings: &Settings,
+    resize: bool,
+) {
+    let fullscreen = if settings.fullscreen {
+        FullscreenType::Desktop
+    } else {
+        FullscreenType::Off
+    };
+    if let Err(err) = window.set_fullscreen(fullscreen) {
+        log::warn!("Could not change the fullscreen mode: {}", err);
+    }
+    if resize && !settings.fullscreen {
+        let (width, height) = settings.window_size;
+        if let Err(err) = window.set_size(width, height) {
+            log::warn!("Could not resize the window: {}", err);
+        }
+    }
+    let interval = if settings.vsync {
+        SwapInterval::VSync
+    } else {
+        SwapInterval::Immediate
+    };
+    if let Err(err) = video.gl_set_swap_interval(interval) {
+        log::warn!("Could not change the vsync setting: {}", err);
+    }
+}
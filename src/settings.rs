@@ -0,0 +1,279 @@
+//! The player's settings, saved as a plain `key = value` file in the
+//! platform's config directory, and edited in the settings screen.
+use crate::world::{GameOptions, TILE_SIZE};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+const SETTINGS_DIRECTORY: &str = "dragons-dungeon";
+const SETTINGS_FILE: &str = "settings.txt";
+
+/// The range of round durations accepted from the settings file, in
+/// seconds.
+const ROUND_DURATION_RANGE: (f32, f32) = (0.01, 2.0);
+const MIN_WINDOW_SIZE: (u32, u32) = (320, 240);
+const MAX_WINDOW_SIZE: (u32, u32) = (7680, 4320);
+
+/// The round durations to pick from, with names for the settings
+/// screen.
+pub const ANIMATION_SPEEDS: [(&str, f32); 4] = [
+    ("Slow", 0.4),
+    ("Normal", 0.2),
+    ("Fast", 0.1),
+    ("Very fast", 0.05),
+];
+pub const TILE_SIZES: [f32; 5] = [32.0, 40.0, 48.0, 56.0, 64.0];
+pub const VOLUMES: [f32; 5] = [0.0, 0.25, 0.5, 0.75, 1.0];
+pub const WINDOW_SIZES: [(u32, u32); 5] = [
+    (800, 600),
+    (1024, 768),
+    (1280, 720),
+    (1600, 900),
+    (1920, 1080),
+];
+
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// How long the animations of a round take, in seconds.
+    pub round_duration: f32,
+    /// Animate the player's move first, and then the monsters' one
+    /// at a time, instead of everything at once.
+    pub sequenced_animations: bool,
+    pub tile_size: f32,
+    /// Have the camera lag a little behind the player, instead of
+    /// following them exactly.
+    pub smooth_camera: bool,
+    /// The size of the window when it's not fullscreen.
+    pub window_size: (u32, u32),
+    pub fullscreen: bool,
+    pub vsync: bool,
+    /// The volume of the sound effects, 0.0 - 1.0.
+    pub sound_volume: f32,
+    /// The volume of the music, 0.0 - 1.0.
+    pub music_volume: f32,
+    pub options: GameOptions,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            round_duration: 0.2,
+            sequenced_animations: true,
+            tile_size: TILE_SIZE,
+            smooth_camera: true,
+            window_size: (800, 600),
+            fullscreen: false,
+            vsync: true,
+            sound_volume: 1.0,
+            music_volume: 0.5,
+            options: GameOptions::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the settings from the settings file. Anything that's
+    /// missing or can't be parsed is left at the default.
+    pub fn load() -> Settings {
+        let mut settings = Settings::default();
+        let text = match settings_path().map(fs::read_to_string) {
+            Some(Ok(text)) => text,
+            _ => return settings,
+        };
+        for line in text.lines() {
+            let mut parts = line.splitn(2, '=').map(str::trim);
+            if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+                if settings.set(key, value).is_none() {
+                    log::warn!("Ignoring setting \"{}\" with value \"{}\"", key, value);
+                }
+            }
+        }
+        settings
+    }
+
+    /// Writes the settings into the settings file.
+    pub fn save(&self) {
+        let path = match settings_path() {
+            Some(path) => path,
+            None => {
+                log::warn!("Can't find a place for the settings file, not saving settings");
+                return;
+            }
+        };
+        if let Some(directory) = path.parent() {
+            if let Err(err) = fs::create_dir_all(directory) {
+                log::warn!("Could not create {}: {}", directory.display(), err);
+                return;
+            }
+        }
+        let text = format!(
+            "round_duration = {}\n\
+             sequenced_animations = {}\n\
+             tile_size = {}\n\
+             smooth_camera = {}\n\
+             window_width = {}\n\
+             window_height = {}\n\
+             fullscreen = {}\n\
+             vsync = {}\n\
+             sound_volume = {}\n\
+             music_volume = {}\n\
+             combat_rolls = {}\n\
+             identification = {}\n\
+             diagonal_movement = {}\n",
+            self.round_duration,
+            self.sequenced_animations,
+            self.tile_size,
+            self.smooth_camera,
+            self.window_size.0,
+            self.window_size.1,
+            self.fullscreen,
+            self.vsync,
+            self.sound_volume,
+            self.music_volume,
+            self.options.combat_rolls,
+            self.options.identification,
+            self.options.diagonal_movement,
+        );
+        if let Err(err) = fs::write(&path, text) {
+            log::warn!("Could not save the settings to {}: {}", path.display(), err);
+        }
+    }
+
+    /// Sets the setting from the settings file. Returns None if the
+    /// key is unknown, or the value can't be parsed or is out of
+    /// range, in which case the setting is left as it was.
+    fn set(&mut self, key: &str, value: &str) -> Option<()> {
+        let (min_tile_size, max_tile_size) = (TILE_SIZES[0], TILE_SIZES[TILE_SIZES.len() - 1]);
+        let (min_duration, max_duration) = ROUND_DURATION_RANGE;
+        match key {
+            "round_duration" => {
+                self.round_duration = in_range(value.parse().ok()?, min_duration, max_duration)?
+            }
+            "sequenced_animations" => self.sequenced_animations = value.parse().ok()?,
+            "tile_size" => {
+                self.tile_size = in_range(value.parse().ok()?, min_tile_size, max_tile_size)?
+            }
+            "smooth_camera" => self.smooth_camera = value.parse().ok()?,
+            "window_width" => {
+                let (min, max) = (MIN_WINDOW_SIZE.0, MAX_WINDOW_SIZE.0);
+                self.window_size.0 = in_range(value.parse().ok()?, min, max)?
+            }
+            "window_height" => {
+                let (min, max) = (MIN_WINDOW_SIZE.1, MAX_WINDOW_SIZE.1);
+                self.window_size.1 = in_range(value.parse().ok()?, min, max)?
+            }
+            "fullscreen" => self.fullscreen = value.parse().ok()?,
+            "vsync" => self.vsync = value.parse().ok()?,
+            "sound_volume" => self.sound_volume = parse_volume(value)?,
+            "music_volume" => self.music_volume = parse_volume(value)?,
+            "combat_rolls" => self.options.combat_rolls = value.parse().ok()?,
+            "identification" => self.options.identification = value.parse().ok()?,
+            "diagonal_movement" => self.options.diagonal_movement = value.parse().ok()?,
+            _ => return None,
+        }
+        Some(())
+    }
+
+    /// The name of the current animation speed, for the settings
+    /// screen.
+    pub fn animation_speed_name(&self) -> &'static str {
+        ANIMATION_SPEEDS
+            .iter()
+            .find(|(_, duration)| (duration - self.round_duration).abs() < 0.001)
+            .map(|(name, _)| *name)
+            .unwrap_or("Custom")
+    }
+
+    pub fn cycle_animation_speed(&mut self, forward: bool) {
+        let durations: Vec<f32> = ANIMATION_SPEEDS.iter().map(|&(_, d)| d).collect();
+        self.round_duration = cycle(&durations, self.round_duration, forward);
+    }
+
+    pub fn cycle_tile_size(&mut self, forward: bool) {
+        self.tile_size = cycle(&TILE_SIZES, self.tile_size, forward);
+    }
+
+    /// Steps to the next bigger (or smaller) tile size, stopping at
+    /// the biggest (or smallest) one. Zooming in also stops at
+    /// `max_tile_size`, if given, since the bigger sizes wouldn't be
+    /// shown anyway.
+    pub fn zoom(&mut self, zoom_in: bool, max_tile_size: Option<f32>) {
+        let max_tile_size = max_tile_size.unwrap_or(f32::INFINITY);
+        let tile_size = self.tile_size.min(max_tile_size);
+        let next = if zoom_in {
+            TILE_SIZES
+                .iter()
+                .find(|&&size| size > tile_size && size <= max_tile_size)
+        } else {
+            TILE_SIZES.iter().rev().find(|&&size| size < tile_size)
+        };
+        if let Some(&next) = next {
+            self.tile_size = next;
+        }
+    }
+
+    pub fn cycle_window_size(&mut self, forward: bool) {
+        self.window_size = cycle(&WINDOW_SIZES, self.window_size, forward);
+    }
+
+    pub fn cycle_sound_volume(&mut self, forward: bool) {
+        self.sound_volume = cycle(&VOLUMES, self.sound_volume, forward);
+    }
+
+    pub fn cycle_music_volume(&mut self, forward: bool) {
+        self.music_volume = cycle(&VOLUMES, self.music_volume, forward);
+    }
+}
+
+/// Returns the value after (or before) `current` in `values`. If
+/// `current` isn't one of them, starts from the first (or last) one.
+fn cycle<T: Copy + PartialEq>(values: &[T], current: T, forward: bool) -> T {
+    let len = values.len();
+    match values.iter().position(|&value| value == current) {
+        Some(i) if forward => values[(i + 1) % len],
+        Some(i) => values[(i + len - 1) % len],
+        None if forward => values[0],
+        None => values[len - 1],
+    }
+}
+
+/// Returns the value if it's within `min..=max`. NaNs are not.
+fn in_range<T: PartialOrd>(value: T, min: T, max: T) -> Option<T> {
+    if min <= value && value <= max {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// Parses a volume, clamped between 0.0 (muted) and 1.0 (full).
+fn parse_volume(value: &str) -> Option<f32> {
+    let volume: f32 = value.parse().ok()?;
+    if volume.is_nan() {
+        None
+    } else {
+        Some(volume.clamp(0.0, 1.0))
+    }
+}
+
+/// The settings file, in the platform's config directory:
+/// `%APPDATA%` on Windows, `~/Library/Preferences` on macOS, and
+/// `$XDG_CONFIG_HOME` or `~/.config` elsewhere.
+fn settings_path() -> Option<PathBuf> {
+    let home = || env::var_os("HOME").map(PathBuf::from);
+    let config_directory = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library").join("Preferences"))
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| home().map(|home| home.join(".config")))
+    }?;
+    Some(
+        config_directory
+            .join(SETTINGS_DIRECTORY)
+            .join(SETTINGS_FILE),
+    )
+}
@@ -5,9 +5,15 @@ pub const WALL: SpriteData = (0 * 2, 16 * 2, 16 * 2, 16 * 2);
 pub const SKELETON: SpriteData = (0 * 2, 48 * 2, 16 * 2, 16 * 2);
 pub const ZOMBIE: SpriteData = (0 * 2, 192 * 2, 16 * 2, 16 * 2);
 pub const DRAGON: SpriteData = (0 * 2, 208 * 2, 16 * 2, 16 * 2);
+pub const SHOPKEEPER: SpriteData = (0 * 2, 416 * 2, 16 * 2, 16 * 2);
 pub const FLAME: SpriteData = (0 * 2, 256 * 2, 16 * 2, 16 * 2);
 pub const DOOR: SpriteData = (0 * 2, 240 * 2, 16 * 2, 16 * 2);
+/// The door, swung open. Shown as it disappears.
+pub const DOOR_OPEN: SpriteData = (48 * 2, 416 * 2, 16 * 2, 16 * 2);
 pub const NEXT_LEVEL: SpriteData = (0 * 2, 272 * 2, 16 * 2, 16 * 2);
+pub const TRAP_SPIKES: SpriteData = (0 * 2, 288 * 2, 16 * 2, 16 * 2);
+pub const TRAP_PRESSURE_PLATE: SpriteData = (0 * 2, 304 * 2, 16 * 2, 16 * 2);
+pub const TRAP_FIRE_VENT: SpriteData = (0 * 2, 320 * 2, 16 * 2, 16 * 2);
 pub const ICONS_HEART: [SpriteData; 4] = [
     (0 * 2, 32 * 2, 6 * 2, 6 * 2),
     (6 * 2, 32 * 2, 6 * 2, 6 * 2),
@@ -22,6 +28,26 @@ pub const ITEM_SHIELD: SpriteData = (0 * 2, 128 * 2, 16 * 2, 16 * 2);
 pub const ITEM_VAMPIRE_TEETH: SpriteData = (0 * 2, 144 * 2, 16 * 2, 16 * 2);
 pub const ITEM_STOPWATCH: SpriteData = (0 * 2, 160 * 2, 16 * 2, 16 * 2);
 pub const ITEM_APPLE: SpriteData = (0 * 2, 224 * 2, 16 * 2, 16 * 2);
-
-#[allow(dead_code)]
+pub const ITEM_GOLD: SpriteData = (32 * 2, 416 * 2, 16 * 2, 16 * 2);
+pub const ITEM_LEATHER_ARMOR: SpriteData = (0 * 2, 384 * 2, 16 * 2, 16 * 2);
+pub const ITEM_CHAIN_MAIL: SpriteData = (16 * 2, 384 * 2, 16 * 2, 16 * 2);
+pub const POTION_HEALING: SpriteData = (0 * 2, 336 * 2, 16 * 2, 16 * 2);
+pub const POTION_HASTE: SpriteData = (16 * 2, 336 * 2, 16 * 2, 16 * 2);
+pub const POTION_STRENGTH: SpriteData = (32 * 2, 336 * 2, 16 * 2, 16 * 2);
+pub const SCROLL_TELEPORT: SpriteData = (0 * 2, 352 * 2, 16 * 2, 16 * 2);
+pub const SCROLL_MAPPING: SpriteData = (16 * 2, 352 * 2, 16 * 2, 16 * 2);
+pub const SCROLL_FEAR: SpriteData = (32 * 2, 352 * 2, 16 * 2, 16 * 2);
+pub const SCROLL_IDENTIFY: SpriteData = (48 * 2, 352 * 2, 16 * 2, 16 * 2);
+pub const POTION_REGENERATION: SpriteData = (48 * 2, 336 * 2, 16 * 2, 16 * 2);
+pub const THROWING_KNIFE: SpriteData = (0 * 2, 368 * 2, 16 * 2, 16 * 2);
+pub const ICON_STUN: SpriteData = (0 * 2, 400 * 2, 8 * 2, 8 * 2);
+pub const ICON_POISON: SpriteData = (8 * 2, 400 * 2, 8 * 2, 8 * 2);
+pub const ICON_HASTE: SpriteData = (16 * 2, 400 * 2, 8 * 2, 8 * 2);
+pub const ICON_STRENGTH: SpriteData = (24 * 2, 400 * 2, 8 * 2, 8 * 2);
+pub const ICON_FEAR: SpriteData = (32 * 2, 400 * 2, 8 * 2, 8 * 2);
+pub const ICON_BURN: SpriteData = (40 * 2, 400 * 2, 8 * 2, 8 * 2);
+pub const ICON_BLEED: SpriteData = (48 * 2, 400 * 2, 8 * 2, 8 * 2);
+pub const ICON_SLOW: SpriteData = (56 * 2, 400 * 2, 8 * 2, 8 * 2);
+pub const ICON_REGENERATION: SpriteData = (64 * 2, 400 * 2, 8 * 2, 8 * 2);
+pub const ICON_WEAKNESS: SpriteData = (72 * 2, 400 * 2, 8 * 2, 8 * 2);
 pub const COBWEB: SpriteData = (0 * 2, 176 * 2, 16 * 2, 16 * 2);
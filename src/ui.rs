@@ -1,9 +1,13 @@
-use crate::layers;
-use crate::world::{Item, World};
+use crate::world::{self, Entity, Item, Perk, PlayerAction, ThrowSource, World};
+use crate::{input, layers};
 use fae::{Alignment, Font, GraphicsContext, Spritesheet};
+use sdl2::keyboard::Keycode;
 use std::sync::Mutex;
 
+pub mod screens;
+
 pub const UI_AREA_WIDTH: f32 = 230.0;
+const MESSAGE_LOG_LINES: usize = 6;
 
 #[derive(Debug, Default)]
 pub struct DebugState {
@@ -118,23 +122,496 @@ impl MenuFlow {
         self.y += 2.0;
     }
 
-    fn print_item(&mut self, ctx: &mut GraphicsContext, font: &Font, item: &Item) {
-        self.print(ctx, font, item.name(), 18.0, 10.0, false);
+    fn print_item(&mut self, ctx: &mut GraphicsContext, font: &Font, world: &World, item: Item) {
+        self.print(ctx, font, world.item_name(item), 18.0, 10.0, false);
         self.y += 6.0;
-        self.print(ctx, font, item.description(), 12.0, 16.0, false);
+        self.print(ctx, font, world.item_description(item), 12.0, 16.0, false);
         self.y += 6.0;
     }
 
+    /// Prints what the player can tell about the entity in the look
+    /// mode, with `state` being what its AI is up to.
+    fn print_entity(
+        &mut self,
+        ctx: &mut GraphicsContext,
+        font: &Font,
+        world: &World,
+        entity: &Entity,
+        state: Option<&str>,
+    ) {
+        if let Some(item) = entity.drop {
+            self.print_item(ctx, font, world, item);
+            if let Some(price) = entity.price {
+                self.print_stat(ctx, font, &format!("Price: {} gold", price));
+            }
+            return;
+        }
+
+        self.print(ctx, font, entity.name, 18.0, 10.0, false);
+        self.y += 6.0;
+        if let Some(health) = &entity.health {
+            if entity.is_alive() {
+                let text = format!("Health: {}/{}", health.current, health.max);
+                self.print_stat(ctx, font, &text);
+            } else {
+                self.print_stat(ctx, font, "Dead");
+            }
+        }
+        if let (Some(damage), true) = (&entity.damage, entity.is_alive()) {
+            let damage = match &entity.inventory {
+                Some(inventory) => inventory.damage_after_items(damage.0),
+                None => damage.0,
+            };
+            self.print_stat(ctx, font, &format!("Damage: {}", damage));
+        }
+        for effect in entity.status_effects.iter().flatten() {
+            let behavior = effect.kind.behavior();
+            let text = format!("{} ({})", behavior.name(), effect.duration);
+            self.print_stat(ctx, font, &text);
+        }
+        if let (Some(state), true) = (state, entity.is_alive()) {
+            self.print_stat(ctx, font, state);
+        }
+        self.print(ctx, font, entity.description, 12.0, 16.0, false);
+        self.y += 6.0;
+    }
+
+    /// Prints the inventory slot, with the (name, description) of
+    /// the item in it, if any.
+    fn print_item_slot(
+        &mut self,
+        ctx: &mut GraphicsContext,
+        font: &Font,
+        slot: usize,
+        item: Option<(&str, &str)>,
+        selected: bool,
+        show_description: bool,
+    ) {
+        let cursor = if selected { "> " } else { "" };
+        let name = item.map(|(name, _)| name).unwrap_or("-");
+        let text = format!("{}{}. {}", cursor, slot + 1, name);
+        self.print(ctx, font, &text, 18.0, 10.0, false);
+        self.y += 6.0;
+        if let (Some((_, description)), true) = (item, show_description) {
+            self.print(ctx, font, description, 12.0, 16.0, false);
+            self.y += 6.0;
+        }
+    }
+
+    /// Prints a horizontal bar, filled up to `progress` (0.0 - 1.0).
+    fn print_bar(
+        &mut self,
+        ctx: &mut GraphicsContext,
+        spritesheet: &Spritesheet,
+        progress: f32,
+        color: (f32, f32, f32, f32),
+    ) {
+        let (x, width, height) = (self.x + 10.0, self.width - 20.0, 8.0);
+        spritesheet
+            .draw(ctx)
+            .coordinates((x, self.y, width, height))
+            .color((0.15, 0.15, 0.15, 1.0))
+            .z(layers::UI_BUTTONS)
+            .finish();
+        spritesheet
+            .draw(ctx)
+            .coordinates((x, self.y, width * progress.clamp(0.0, 1.0), height))
+            .color(color)
+            .z(layers::UI_BUTTONS)
+            .finish();
+        self.y += height + 4.0;
+    }
+
+    /// Reserves `height` pixels of the flow for drawing something
+    /// other than text, and returns the area as (x, y, width, height).
+    fn reserve(&mut self, height: f32) -> (f32, f32, f32, f32) {
+        let area = (self.x + 10.0, self.y, self.width - 20.0, height);
+        self.y += height + 4.0;
+        area
+    }
+
     fn space(&mut self) {
         self.y += 20.0;
     }
 }
 
-pub struct Ui {}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UiMode {
+    Playing,
+    /// Browsing the inventory, with the cursor on the given slot.
+    Inventory(usize),
+    /// Choosing which item to swap out for the one on the floor, when
+    /// the inventory is full.
+    ReplaceItem(usize),
+    /// Aiming a throw at the tile (`x`, `y`).
+    Targeting {
+        source: ThrowSource,
+        x: i32,
+        y: i32,
+    },
+    /// Picking a perk after a level up, with the cursor on the given
+    /// perk.
+    LevelUp(usize),
+    /// Choosing which way to bash with the shield.
+    ShieldBash,
+    /// Looking at the full-screen map.
+    Map,
+    /// Examining the tile (`x`, `y`).
+    Look {
+        x: i32,
+        y: i32,
+    },
+}
+
+pub struct Ui {
+    pub mode: UiMode,
+}
 
 impl Ui {
     pub fn new() -> Ui {
-        Ui {}
+        Ui {
+            mode: UiMode::Playing,
+        }
+    }
+
+    /// Returns true if the key presses should go to Ui::input instead
+    /// of being played out as actions.
+    pub fn is_capturing_input(&self) -> bool {
+        self.mode != UiMode::Playing
+    }
+
+    pub fn input(&mut self, keycode: Keycode, world: &World) -> Option<PlayerAction> {
+        match self.mode {
+            UiMode::Targeting { .. } => self.targeting_input(keycode, world),
+            UiMode::LevelUp(_) => self.level_up_input(keycode),
+            UiMode::ShieldBash => self.shield_bash_input(keycode, world),
+            UiMode::Look { .. } => self.look_input(keycode, world),
+            UiMode::Map => {
+                if input::is_key_map(keycode) || input::is_key_cancel(keycode) {
+                    self.mode = UiMode::Playing;
+                }
+                None
+            }
+            _ => self.inventory_input(keycode, world),
+        }
+    }
+
+    /// Starts choosing a direction for a shield bash, if the player
+    /// has a shield.
+    pub fn start_shield_bash(&mut self, world: &World) {
+        let inventory = world.player().inventory.as_ref();
+        if inventory.iter().any(|inv| inv.has_item(Item::Shield)) {
+            self.mode = UiMode::ShieldBash;
+        }
+    }
+
+    fn shield_bash_input(&mut self, keycode: Keycode, world: &World) -> Option<PlayerAction> {
+        let diagonal = world.options().diagonal_movement;
+        if let Some((xd, yd)) = input::get_key_direction(keycode, diagonal) {
+            self.mode = UiMode::Playing;
+            Some(PlayerAction::ShieldBash(xd, yd))
+        } else {
+            if input::is_key_cancel(keycode) || input::is_key_bash(keycode) {
+                self.mode = UiMode::Playing;
+            }
+            None
+        }
+    }
+
+    /// Opens the perk selection if the player has leveled up.
+    pub fn update(&mut self, world: &World) {
+        if self.mode == UiMode::Playing && world.has_unspent_perks() {
+            self.mode = UiMode::LevelUp(0);
+        }
+    }
+
+    fn level_up_input(&mut self, keycode: Keycode) -> Option<PlayerAction> {
+        let mut cursor = match self.mode {
+            UiMode::LevelUp(cursor) => cursor,
+            _ => return None,
+        };
+        let perks = Perk::ALL.len();
+        let mut action = None;
+        if input::is_key_move_up(keycode) {
+            cursor = (cursor + perks - 1) % perks;
+        } else if input::is_key_move_down(keycode) {
+            cursor = (cursor + 1) % perks;
+        } else if let Some(slot) = input::get_key_slot(keycode) {
+            cursor = slot.min(perks - 1);
+        } else if input::is_key_confirm(keycode) {
+            action = Some(PlayerAction::ChoosePerk(Perk::ALL[cursor]));
+        }
+
+        self.mode = if action.is_some() {
+            UiMode::Playing
+        } else {
+            UiMode::LevelUp(cursor)
+        };
+        action
+    }
+
+    /// Starts aiming a throw with the item from `source`, with the
+    /// cursor on the nearest monster.
+    pub fn start_targeting(&mut self, source: ThrowSource, world: &World) {
+        let player = &world.player().position;
+        let (x, y) = world.nearest_throw_target().unwrap_or((player.x, player.y));
+        self.mode = UiMode::Targeting { source, x, y };
+    }
+
+    /// Uses the consumable in the belt slot, or starts aiming it if
+    /// it's meant to be thrown.
+    pub fn use_belt_slot(&mut self, slot: usize, world: &World) -> Option<PlayerAction> {
+        let inventory = world.player().inventory.as_ref()?;
+        let consumable = inventory.belt.get(slot)?;
+        if consumable.is_throwable() {
+            self.start_targeting(ThrowSource::Belt(slot), world);
+            None
+        } else {
+            Some(PlayerAction::Use(slot))
+        }
+    }
+
+    /// Starts aiming the first throwable on the belt, if there is one.
+    pub fn throw_from_belt(&mut self, world: &World) {
+        let slot = world.player().inventory.as_ref().and_then(|inventory| {
+            inventory
+                .belt
+                .iter()
+                .position(|consumable| consumable.is_throwable())
+        });
+        if let Some(slot) = slot {
+            self.start_targeting(ThrowSource::Belt(slot), world);
+        }
+    }
+
+    /// Starts examining tiles, with the cursor on the player.
+    pub fn start_looking(&mut self, world: &World) {
+        let player = &world.player().position;
+        self.mode = UiMode::Look {
+            x: player.x,
+            y: player.y,
+        };
+    }
+
+    fn look_input(&mut self, keycode: Keycode, world: &World) -> Option<PlayerAction> {
+        if let UiMode::Look { x, y } = self.mode {
+            let diagonal = world.options().diagonal_movement;
+            if let Some((xd, yd)) = input::get_key_direction(keycode, diagonal) {
+                self.mode = UiMode::Look {
+                    x: x + xd,
+                    y: y + yd,
+                };
+            } else if input::is_key_look(keycode) || input::is_key_cancel(keycode) {
+                self.mode = UiMode::Playing;
+            }
+        }
+        None
+    }
+
+    /// Moves the targeting or looking cursor to the tile under the
+    /// mouse, which is at (`x`, `y`) in a window of the given size.
+    pub fn mouse_moved(&mut self, x: f32, y: f32, width: f32, height: f32, world: &World) {
+        let (x, y) = world.screen_to_tile(x, y, width - UI_AREA_WIDTH, height);
+        match self.mode {
+            UiMode::Targeting { source, .. } => {
+                let (x, y) = clamp_to_throw_range(world, x, y);
+                self.mode = UiMode::Targeting { source, x, y };
+            }
+            UiMode::Look { .. } => self.mode = UiMode::Look { x, y },
+            _ => {}
+        }
+    }
+
+    /// Handles mouse clicks: the left button throws, others cancel.
+    pub fn mouse_clicked(&mut self, left_button: bool, world: &World) -> Option<PlayerAction> {
+        if let UiMode::Targeting { source, x, y } = self.mode {
+            if !left_button {
+                self.mode = UiMode::Playing;
+            } else if is_valid_throw_target(world, x, y) {
+                self.mode = UiMode::Playing;
+                return Some(PlayerAction::Throw(source, x, y));
+            }
+        }
+        None
+    }
+
+    fn targeting_input(&mut self, keycode: Keycode, world: &World) -> Option<PlayerAction> {
+        let (source, mut x, mut y) = match self.mode {
+            UiMode::Targeting { source, x, y } => (source, x, y),
+            _ => return None,
+        };
+        let mut action = None;
+        let mut close = false;
+        let diagonal = world.options().diagonal_movement;
+        if let Some((xd, yd)) = input::get_key_direction(keycode, diagonal) {
+            x += xd;
+            y += yd;
+        } else if input::is_key_confirm(keycode) || input::is_key_throw(keycode) {
+            if is_valid_throw_target(world, x, y) {
+                action = Some(PlayerAction::Throw(source, x, y));
+                close = true;
+            }
+        } else if input::is_key_cancel(keycode) {
+            close = true;
+        }
+
+        self.mode = if close {
+            UiMode::Playing
+        } else {
+            let (x, y) = clamp_to_throw_range(world, x, y);
+            UiMode::Targeting { source, x, y }
+        };
+        action
+    }
+
+    /// Handles the key presses while the inventory is open, returning
+    /// the action chosen, if any.
+    pub fn inventory_input(&mut self, keycode: Keycode, world: &World) -> Option<PlayerAction> {
+        let (mut cursor, replacing) = match self.mode {
+            UiMode::Inventory(cursor) => (cursor, false),
+            UiMode::ReplaceItem(cursor) => (cursor, true),
+            _ => return None,
+        };
+        let inventory = world.player().inventory.as_ref()?;
+        let slots = inventory.slots.max(1);
+        let slot_taken = cursor < inventory.items.len();
+
+        let mut action = None;
+        let mut close = false;
+        if input::is_key_move_up(keycode) {
+            cursor = (cursor + slots - 1) % slots;
+        } else if input::is_key_move_down(keycode) {
+            cursor = (cursor + 1) % slots;
+        } else if let Some(slot) = input::get_key_slot(keycode) {
+            cursor = slot.min(slots - 1);
+        } else if input::is_key_confirm(keycode) || input::is_key_pickup(keycode) {
+            if world.pickup_under_player().is_some() {
+                action = Some(PlayerAction::Swap(cursor));
+                close = true;
+            }
+        } else if input::is_key_drop(keycode) && !replacing {
+            if slot_taken {
+                action = Some(PlayerAction::Drop(cursor));
+            }
+        } else if input::is_key_throw(keycode) && !replacing {
+            if slot_taken {
+                self.start_targeting(ThrowSource::Inventory(cursor), world);
+                return None;
+            }
+        } else if input::is_key_cancel(keycode) || input::is_key_inventory(keycode) {
+            close = true;
+        }
+
+        self.mode = if close {
+            UiMode::Playing
+        } else if replacing {
+            UiMode::ReplaceItem(cursor)
+        } else {
+            UiMode::Inventory(cursor)
+        };
+        action
+    }
+
+    fn render_message_log(
+        &self,
+        ctx: &mut GraphicsContext,
+        font: &Font,
+        spritesheet: &Spritesheet,
+        world: &World,
+    ) {
+        let messages: Vec<&String> = world.recent_messages(MESSAGE_LOG_LINES).collect();
+        if messages.is_empty() {
+            return;
+        }
+        let font_size = 14.0;
+        let padding = 10.0;
+        let width = (ctx.width - UI_AREA_WIDTH - padding * 4.0).min(500.0);
+        let height = messages.len() as f32 * (font_size + 2.0) + padding;
+        spritesheet
+            .draw(ctx)
+            .coordinates((padding, padding, width, height))
+            .color((0.01, 0.01, 0.01, 0.7))
+            .z(layers::UI_BG)
+            .finish();
+        let mut y = padding * 1.5;
+        for (i, message) in messages.iter().enumerate() {
+            // Older messages fade out
+            let alpha = 0.4 + 0.6 * (i + 1) as f32 / messages.len() as f32;
+            font.draw(ctx, message.as_str(), padding * 1.5, y, font_size)
+                .color((1.0, 1.0, 1.0, alpha))
+                .max_width(width - padding)
+                .z(layers::UI_TEXT)
+                .finish();
+            y += font_size + 2.0;
+        }
+    }
+
+    fn render_map_overlay(
+        &self,
+        ctx: &mut GraphicsContext,
+        font: &Font,
+        spritesheet: &Spritesheet,
+        world: &World,
+    ) {
+        let padding = 10.0;
+        let (width, height) = (
+            ctx.width - UI_AREA_WIDTH - padding * 4.0,
+            ctx.height - padding * 2.0,
+        );
+        let mut menu = MenuFlow::new(ctx, spritesheet, padding, padding, width, height, padding);
+        menu.print_header(ctx, font, "Map");
+        menu.print_text(ctx, font, "M or Esc: close");
+        let area_height = height - (menu.y - padding) - padding * 2.0;
+        let area = menu.reserve(area_height);
+        world.render_map(ctx, spritesheet, area);
+    }
+
+    fn render_look_cursor(
+        &self,
+        ctx: &mut GraphicsContext,
+        spritesheet: &Spritesheet,
+        world: &World,
+        x: i32,
+        y: i32,
+    ) {
+        let (width, height) = (ctx.width - UI_AREA_WIDTH, ctx.height);
+        let (screen_x, screen_y) = world.tile_to_screen(x, y, width, height);
+        let tile_size = world.view_tile_size(width, height);
+        spritesheet
+            .draw(ctx)
+            .coordinates((screen_x, screen_y, tile_size, tile_size))
+            .color((0.3, 0.7, 0.9, 0.4))
+            .z(layers::CURSOR)
+            .finish();
+    }
+
+    fn render_throw_line(
+        &self,
+        ctx: &mut GraphicsContext,
+        spritesheet: &Spritesheet,
+        world: &World,
+        target_x: i32,
+        target_y: i32,
+    ) {
+        let (width, height) = (ctx.width - UI_AREA_WIDTH, ctx.height);
+        let tile_size = world.view_tile_size(width, height);
+        let player = &world.player().position;
+        let tiles = world::line(player.x, player.y, target_x, target_y);
+        for (x, y) in tiles.into_iter().skip(1) {
+            let (screen_x, screen_y) = world.tile_to_screen(x, y, width, height);
+            let (size, color) = if (x, y) == (target_x, target_y) {
+                (tile_size, (0.9, 0.8, 0.2, 0.4))
+            } else {
+                (tile_size / 4.0, (0.9, 0.8, 0.2, 0.6))
+            };
+            let margin = (tile_size - size) / 2.0;
+            spritesheet
+                .draw(ctx)
+                .coordinates((screen_x + margin, screen_y + margin, size, size))
+                .color(color)
+                .z(layers::CURSOR)
+                .finish();
+        }
     }
 
     pub fn render(
@@ -143,8 +620,6 @@ impl Ui {
         font: &Font,
         spritesheet: &Spritesheet,
         world: &World,
-        game_over: bool,
-        victory: bool,
         show_debug_info: bool,
     ) {
         let (width, height) = (ctx.width, ctx.height);
@@ -167,46 +642,202 @@ impl Ui {
         let entities = world.entities();
 
         // Stats:
-        if let (Some(damage), Some(health), Some(inv)) =
+        if let (Some(_), Some(health), Some(inv)) =
             (&player.damage, &player.health, &player.inventory)
         {
             menu.print_header(ctx, font, "Stats:");
+            menu.print_stat(ctx, font, &format!("Damage: {}", player.effective_damage()));
             menu.print_stat(
                 ctx,
                 font,
-                &format!("Damage: {}", inv.damage_after_items(damage.0)),
+                &format!("Health: {}/{}", health.current, health.max),
             );
+            menu.print_stat(ctx, font, &format!("Gold: {}", inv.gold));
+            if let Some(experience) = &player.experience {
+                let (xp, xp_needed) = (experience.xp, experience.xp_to_next_level());
+                menu.print_stat(
+                    ctx,
+                    font,
+                    &format!("Level {}, XP: {}/{}", experience.level, xp, xp_needed),
+                );
+                let progress = xp as f32 / xp_needed as f32;
+                menu.print_bar(ctx, spritesheet, progress, (0.3, 0.6, 0.9, 1.0));
+            }
+            let defense = player.effective_defense();
             menu.print_stat(
                 ctx,
                 font,
-                &format!("Health: {}/{}", health.current, health.max),
+                &format!("Defense: {} + {}%", defense.flat, defense.percent),
             );
+            for effect in player.status_effects.iter().flatten() {
+                let behavior = effect.kind.behavior();
+                if behavior.icon().is_none() {
+                    continue;
+                }
+                let text = if effect.stacks > 1 {
+                    format!(
+                        "{} x{} ({})",
+                        behavior.name(),
+                        effect.stacks,
+                        effect.duration
+                    )
+                } else {
+                    format!("{} ({})", behavior.name(), effect.duration)
+                };
+                menu.print_stat(ctx, font, &text);
+            }
+            if let (true, Some(stats)) = (world.options().combat_rolls, &player.combat_stats) {
+                menu.print_stat(
+                    ctx,
+                    font,
+                    &format!(
+                        "Hit {}%, dodge {}%, crit {}%",
+                        stats.accuracy, stats.dodge, stats.critical
+                    ),
+                );
+            }
         }
         menu.space();
 
+        // Minimap:
+        if self.mode != UiMode::Map {
+            menu.print_header(ctx, font, "Map [M]:");
+            let area = menu.reserve(menu.width * 0.6);
+            world.render_map(ctx, spritesheet, area);
+            menu.space();
+        }
+
+        // Level up
+        if let UiMode::LevelUp(cursor) = self.mode {
+            menu.print_header(ctx, font, "Level up! Pick a perk:");
+            for (i, perk) in Perk::ALL.iter().enumerate() {
+                let selected = if i == cursor { "> " } else { "" };
+                let text = format!("{}{}. {}", selected, i + 1, perk.name());
+                menu.print(ctx, font, &text, 18.0, 10.0, false);
+                menu.y += 6.0;
+                if i == cursor {
+                    menu.print(ctx, font, perk.description(), 12.0, 16.0, false);
+                    menu.y += 6.0;
+                }
+            }
+            menu.print_text(ctx, font, "Enter: pick");
+            menu.space();
+        }
+
         // Inventory
         if let Some(inventory) = &player.inventory {
-            if !inventory.is_empty() {
-                menu.print_header(ctx, font, "Inventory:");
+            let cursor = match self.mode {
+                UiMode::Inventory(cursor) | UiMode::ReplaceItem(cursor) => Some(cursor),
+                UiMode::Targeting {
+                    source: ThrowSource::Inventory(cursor),
+                    ..
+                } => Some(cursor),
+                _ => None,
+            };
+            if cursor.is_some() || !inventory.is_empty() {
+                match self.mode {
+                    UiMode::Playing => menu.print_header(ctx, font, "Inventory [I]:"),
+                    UiMode::ReplaceItem(_) => menu.print_header(ctx, font, "Replace which?"),
+                    _ => menu.print_header(ctx, font, "Inventory:"),
+                }
+
+                for slot in 0..inventory.slots {
+                    let item = inventory
+                        .items
+                        .get(slot)
+                        .map(|&item| (world.item_name(item), world.item_description(item)));
+                    if item.is_none() && cursor.is_none() {
+                        continue;
+                    }
+                    let selected = cursor == Some(slot);
+                    let show_description = cursor.is_none() || selected;
+                    menu.print_item_slot(ctx, font, slot, item, selected, show_description);
+                }
 
-                if let Some(item) = &inventory.item_left {
-                    menu.print_item(ctx, font, item);
+                match self.mode {
+                    UiMode::Inventory(_) => menu.print_text(
+                        ctx,
+                        font,
+                        "Enter: swap with the floor, X: drop, T: throw, Esc: close",
+                    ),
+                    UiMode::ReplaceItem(_) => {
+                        menu.print_text(ctx, font, "Enter: swap with the floor, Esc: cancel")
+                    }
+                    _ => {}
                 }
-                if let Some(item) = &inventory.item_right {
-                    menu.print_item(ctx, font, item);
+                menu.space();
+            }
+        }
+
+        // Belt
+        if let Some(inventory) = &player.inventory {
+            if !inventory.belt.is_empty() {
+                menu.print_header(ctx, font, "Belt:");
+                for (slot, consumable) in inventory.belt.iter().enumerate() {
+                    let name = world.item_name(Item::Consumable(*consumable));
+                    let text = format!("[{}] {}", slot + 1, name);
+                    menu.print_stat(ctx, font, &text);
                 }
+                menu.space();
             }
         }
-        menu.space();
+
+        // Targeting:
+        if let UiMode::Targeting { x, y, .. } = self.mode {
+            menu.print_header(ctx, font, "Throw where?");
+            menu.print_text(
+                ctx,
+                font,
+                "Move the cursor with the movement keys or the mouse. Enter or click: throw, Esc: cancel",
+            );
+            self.render_throw_line(ctx, spritesheet, world, x, y);
+            menu.space();
+        }
+
+        // Look:
+        if let UiMode::Look { x, y } = self.mode {
+            menu.print_header(ctx, font, "Look");
+            let seen = world.look_at(x, y);
+            if seen.is_empty() {
+                menu.print_text(ctx, font, "Nothing you can make out.");
+            }
+            for (entity, state) in seen {
+                menu.print_entity(ctx, font, world, entity, state);
+            }
+            menu.print_text(
+                ctx,
+                font,
+                "Move the cursor with the movement keys or the mouse. Esc: stop looking",
+            );
+            self.render_look_cursor(ctx, spritesheet, world, x, y);
+            menu.space();
+        }
+
+        // Shield bash:
+        if self.mode == UiMode::ShieldBash {
+            menu.print_header(ctx, font, "Bash which way?");
+            menu.print_text(ctx, font, "Movement keys: bash, Esc: cancel");
+            menu.space();
+        }
 
         // Pickups:
-        for pickup in entities
+        for (pickup, price) in entities
             .iter()
             .filter(|e| e.position.x == player.position.x && e.position.y == player.position.y)
-            .filter_map(|e| e.drop)
+            .filter_map(|e| e.drop.map(|item| (item, e.price)))
         {
-            menu.print_header(ctx, font, "Pick up [,]:");
-            menu.print_item(ctx, font, &pickup);
+            match (pickup, price) {
+                (_, Some(price)) => {
+                    let header = format!("Buy for {} gold [,]:", price);
+                    menu.print_header(ctx, font, &header);
+                }
+                (Item::Gold(amount), None) => {
+                    let header = format!("Pick up {} gold [,]:", amount);
+                    menu.print_header(ctx, font, &header);
+                }
+                _ => menu.print_header(ctx, font, "Pick up [,]:"),
+            }
+            menu.print_item(ctx, font, world, pickup);
         }
         menu.space();
 
@@ -225,34 +856,10 @@ impl Ui {
         }
         menu.space();
 
-        if game_over {
-            let width = 410.0;
-            let height = 180.0;
-            let menu_x = (ctx.width - UI_AREA_WIDTH - padding * 2.0) / 2.0 - width / 2.0;
-            let menu_y = ctx.height / 2.0 - height / 2.0;
-            let mut menu = MenuFlow::new(ctx, spritesheet, menu_x, menu_y, width, height, 10.0);
-            menu.space();
-            menu.print_header_centered(ctx, font, "Game over.");
-            menu.space();
-            menu.print_text(ctx, font, "Your adventure has ended in failure.");
-            menu.print_text(ctx, font, "Better luck next time!");
-            menu.space();
-            menu.print_text(ctx, font, "Press R to retry.");
-        }
-
-        if victory {
-            let width = 410.0;
-            let height = 220.0;
-            let menu_x = (ctx.width - UI_AREA_WIDTH - padding * 2.0) / 2.0 - width / 2.0;
-            let menu_y = ctx.height / 2.0 - height / 2.0;
-            let mut menu = MenuFlow::new(ctx, spritesheet, menu_x, menu_y, width, height, 10.0);
-            menu.space();
-            menu.print_header_centered(ctx, font, "Victory!");
-            menu.space();
-            menu.print_text(ctx, font, "You have defeated the dragon!");
-            menu.print_text(ctx, font, "Congratulations!");
-            menu.space();
-            menu.print_text(ctx, font, "Press R to try your luck with the next one.");
+        if self.mode == UiMode::Map {
+            self.render_map_overlay(ctx, font, spritesheet, world);
+        } else {
+            self.render_message_log(ctx, font, spritesheet, world);
         }
 
         if show_debug_info {
@@ -285,3 +892,20 @@ impl Ui {
         }
     }
 }
+
+/// Keeps the target within the player's throwing range.
+fn clamp_to_throw_range(world: &World, x: i32, y: i32) -> (i32, i32) {
+    let player = &world.player().position;
+    let range = world::THROW_RANGE;
+    (
+        x.clamp(player.x - range, player.x + range),
+        y.clamp(player.y - range, player.y + range),
+    )
+}
+
+/// Whether the item can be thrown at (`x`, `y`). The player's own tile
+/// isn't a target, as the item would have nowhere to fly.
+fn is_valid_throw_target(world: &World, x: i32, y: i32) -> bool {
+    let player = &world.player().position;
+    (x, y) != (player.x, player.y)
+}
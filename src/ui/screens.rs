@@ -0,0 +1,358 @@
+//! The screens around the game itself: the title screen, the pause
+//! menu, the settings, the end screens and the quit confirmation.
+//! They're kept in a stack, and the input goes to the one on top.
+use super::{MenuFlow, UI_AREA_WIDTH};
+use crate::input;
+use crate::settings::Settings;
+use fae::{Font, GraphicsContext, Spritesheet};
+use sdl2::keyboard::Keycode;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Screen {
+    Title,
+    /// The game itself, which takes its input through Ui and the
+    /// game loop instead of the screen stack.
+    Playing,
+    Pause,
+    Settings,
+    GameOver,
+    Victory,
+    QuitConfirm,
+}
+
+/// The things the screens need the game loop to do.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScreenCommand {
+    NewGame,
+    /// The settings were changed, and should be applied and saved.
+    ApplySettings,
+    Quit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MenuItem {
+    Continue,
+    NewGame,
+    Resume,
+    Settings,
+    TitleScreen,
+    Quit,
+    AnimationSpeed,
+    SequencedAnimations,
+    TileSize,
+    SmoothCamera,
+    WindowSize,
+    Fullscreen,
+    Vsync,
+    SoundVolume,
+    MusicVolume,
+    CombatRolls,
+    Identification,
+    DiagonalMovement,
+    Back,
+    ConfirmQuit,
+}
+
+impl MenuItem {
+    fn label(self, settings: &Settings) -> String {
+        let on_off = |enabled| if enabled { "On" } else { "Off" };
+        let volume = |volume: f32| (volume * 100.0).round() as i32;
+        let options = &settings.options;
+        match self {
+            MenuItem::Continue => "Continue".to_string(),
+            MenuItem::NewGame => "New game".to_string(),
+            MenuItem::Resume => "Resume".to_string(),
+            MenuItem::Settings => "Settings".to_string(),
+            MenuItem::TitleScreen => "Back to the title screen".to_string(),
+            MenuItem::Quit => "Quit".to_string(),
+            MenuItem::AnimationSpeed => {
+                format!("Animation speed: {}", settings.animation_speed_name())
+            }
+            MenuItem::SequencedAnimations if settings.sequenced_animations => {
+                "Animations: One at a time".to_string()
+            }
+            MenuItem::SequencedAnimations => "Animations: All at once".to_string(),
+            MenuItem::TileSize => format!("Tile size: {}", settings.tile_size),
+            MenuItem::SmoothCamera if settings.smooth_camera => "Camera: Smooth".to_string(),
+            MenuItem::SmoothCamera => "Camera: Fixed to the player".to_string(),
+            MenuItem::WindowSize => {
+                let (width, height) = settings.window_size;
+                format!("Window size: {}x{}", width, height)
+            }
+            MenuItem::Fullscreen => format!("Fullscreen: {}", on_off(settings.fullscreen)),
+            MenuItem::Vsync => format!("VSync: {}", on_off(settings.vsync)),
+            MenuItem::SoundVolume => format!("Sound volume: {}%", volume(settings.sound_volume)),
+            MenuItem::MusicVolume => format!("Music volume: {}%", volume(settings.music_volume)),
+            MenuItem::CombatRolls => format!("Combat rolls: {}", on_off(options.combat_rolls)),
+            MenuItem::Identification => {
+                format!("Identification: {}", on_off(options.identification))
+            }
+            MenuItem::DiagonalMovement => {
+                format!("Diagonal movement: {}", on_off(options.diagonal_movement))
+            }
+            MenuItem::Back => "Back".to_string(),
+            MenuItem::ConfirmQuit => "Yes, quit".to_string(),
+        }
+    }
+}
+
+/// The menu items of the screen. Continue is only offered if there's
+/// a game that can be continued.
+fn menu_items(screen: Screen, game_in_progress: bool) -> Vec<MenuItem> {
+    match screen {
+        Screen::Title if game_in_progress => vec![
+            MenuItem::Continue,
+            MenuItem::NewGame,
+            MenuItem::Settings,
+            MenuItem::Quit,
+        ],
+        Screen::Title => vec![MenuItem::NewGame, MenuItem::Settings, MenuItem::Quit],
+        Screen::Playing => Vec::new(),
+        Screen::Pause => vec![
+            MenuItem::Resume,
+            MenuItem::Settings,
+            MenuItem::TitleScreen,
+            MenuItem::Quit,
+        ],
+        Screen::Settings => vec![
+            MenuItem::AnimationSpeed,
+            MenuItem::SequencedAnimations,
+            MenuItem::TileSize,
+            MenuItem::SmoothCamera,
+            MenuItem::WindowSize,
+            MenuItem::Fullscreen,
+            MenuItem::Vsync,
+            MenuItem::SoundVolume,
+            MenuItem::MusicVolume,
+            MenuItem::CombatRolls,
+            MenuItem::Identification,
+            MenuItem::DiagonalMovement,
+            MenuItem::Back,
+        ],
+        Screen::GameOver | Screen::Victory => {
+            vec![MenuItem::NewGame, MenuItem::TitleScreen, MenuItem::Quit]
+        }
+        Screen::QuitConfirm => vec![MenuItem::ConfirmQuit, MenuItem::Back],
+    }
+}
+
+pub struct Screens {
+    /// The screens, with the cursor position of their menus. The last
+    /// one is on top.
+    stack: Vec<(Screen, usize)>,
+}
+
+impl Screens {
+    pub fn new() -> Screens {
+        Screens {
+            stack: vec![(Screen::Title, 0)],
+        }
+    }
+
+    pub fn top(&self) -> Screen {
+        self.stack
+            .last()
+            .map(|&(screen, _)| screen)
+            .unwrap_or(Screen::Title)
+    }
+
+    /// Returns true if the game is being played, as opposed to being
+    /// paused or over.
+    pub fn is_playing(&self) -> bool {
+        self.top() == Screen::Playing
+    }
+
+    /// Returns true if the game should be drawn under the screens.
+    pub fn is_in_game(&self) -> bool {
+        self.stack.first().map(|&(screen, _)| screen) == Some(Screen::Playing)
+    }
+
+    /// Drops all the screens and goes to the game.
+    pub fn start_game(&mut self) {
+        self.stack = vec![(Screen::Playing, 0)];
+    }
+
+    pub fn pause(&mut self) {
+        if self.is_playing() {
+            self.stack.push((Screen::Pause, 0));
+        }
+    }
+
+    /// Opens the game over or victory screen, if the game has just
+    /// ended.
+    pub fn update(&mut self, game_over: bool, victory: bool) {
+        if self.is_playing() {
+            if game_over {
+                self.stack.push((Screen::GameOver, 0));
+            } else if victory {
+                self.stack.push((Screen::Victory, 0));
+            }
+        }
+    }
+
+    /// Handles the key presses for the screen on top, returning what
+    /// the game loop should do, if anything.
+    pub fn input(
+        &mut self,
+        keycode: Keycode,
+        settings: &mut Settings,
+        game_in_progress: bool,
+    ) -> Option<ScreenCommand> {
+        let screen = self.top();
+        let items = menu_items(screen, game_in_progress);
+        let cursor = match self.stack.last_mut() {
+            Some((_, cursor)) if !items.is_empty() => cursor,
+            _ => return None,
+        };
+        *cursor = (*cursor).min(items.len() - 1);
+
+        if input::is_key_move_up(keycode) {
+            *cursor = (*cursor + items.len() - 1) % items.len();
+        } else if input::is_key_move_down(keycode) {
+            *cursor = (*cursor + 1) % items.len();
+        } else if input::is_key_confirm(keycode) {
+            let item = items[*cursor];
+            return self.select(item, settings, true);
+        } else if input::is_key_cancel(keycode) {
+            self.back();
+        } else if screen == Screen::Settings
+            && (input::is_key_move_left(keycode) || input::is_key_move_right(keycode))
+        {
+            let item = items[*cursor];
+            if item != MenuItem::Back {
+                let forward = input::is_key_move_right(keycode);
+                return self.select(item, settings, forward);
+            }
+        } else if input::is_key_restart(keycode)
+            && (screen == Screen::GameOver || screen == Screen::Victory)
+        {
+            return Some(ScreenCommand::NewGame);
+        }
+        None
+    }
+
+    /// Does what the menu item says. The settings cycle forward or
+    /// backward through their values.
+    fn select(
+        &mut self,
+        item: MenuItem,
+        settings: &mut Settings,
+        forward: bool,
+    ) -> Option<ScreenCommand> {
+        let options = &mut settings.options;
+        match item {
+            MenuItem::Continue => self.start_game(),
+            MenuItem::NewGame => return Some(ScreenCommand::NewGame),
+            MenuItem::Resume | MenuItem::Back => self.back(),
+            MenuItem::Settings => self.stack.push((Screen::Settings, 0)),
+            MenuItem::TitleScreen => self.stack = vec![(Screen::Title, 0)],
+            MenuItem::Quit => self.stack.push((Screen::QuitConfirm, 0)),
+            MenuItem::ConfirmQuit => return Some(ScreenCommand::Quit),
+            MenuItem::AnimationSpeed => settings.cycle_animation_speed(forward),
+            MenuItem::SequencedAnimations => {
+                settings.sequenced_animations = !settings.sequenced_animations
+            }
+            MenuItem::TileSize => settings.cycle_tile_size(forward),
+            MenuItem::SmoothCamera => settings.smooth_camera = !settings.smooth_camera,
+            MenuItem::WindowSize => settings.cycle_window_size(forward),
+            MenuItem::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            MenuItem::Vsync => settings.vsync = !settings.vsync,
+            MenuItem::SoundVolume => settings.cycle_sound_volume(forward),
+            MenuItem::MusicVolume => settings.cycle_music_volume(forward),
+            MenuItem::CombatRolls => options.combat_rolls = !options.combat_rolls,
+            MenuItem::Identification => options.identification = !options.identification,
+            MenuItem::DiagonalMovement => options.diagonal_movement = !options.diagonal_movement,
+        }
+        if self.top() == Screen::Settings {
+            Some(ScreenCommand::ApplySettings)
+        } else {
+            None
+        }
+    }
+
+    /// Closes the screen on top. The title screen asks whether to
+    /// quit instead, and the end screens stay until something is
+    /// picked from their menus.
+    fn back(&mut self) {
+        match self.top() {
+            Screen::Title => self.stack.push((Screen::QuitConfirm, 0)),
+            Screen::Pause | Screen::Settings | Screen::QuitConfirm => {
+                self.stack.pop();
+            }
+            Screen::Playing | Screen::GameOver | Screen::Victory => {}
+        }
+    }
+
+    pub fn render(
+        &self,
+        ctx: &mut GraphicsContext,
+        font: &Font,
+        spritesheet: &Spritesheet,
+        settings: &Settings,
+        game_in_progress: bool,
+    ) {
+        let (screen, cursor) = match self.stack.last() {
+            Some(&(Screen::Playing, _)) | None => return,
+            Some(&top) => top,
+        };
+        let items = menu_items(screen, game_in_progress);
+
+        let padding = 10.0;
+        let width = 410.0;
+        let height = 170.0 + items.len() as f32 * 24.0;
+        // In game, the menus are centered on the world, not the
+        // whole window with the side panel.
+        let area_width = if self.is_in_game() {
+            ctx.width - UI_AREA_WIDTH - padding * 2.0
+        } else {
+            ctx.width
+        };
+        let menu_x = area_width / 2.0 - width / 2.0;
+        let menu_y = ctx.height / 2.0 - height / 2.0;
+        let mut menu = MenuFlow::new(ctx, spritesheet, menu_x, menu_y, width, height, padding);
+        menu.space();
+
+        match screen {
+            Screen::Title => {
+                menu.print_header_centered(ctx, font, crate::TITLE);
+                menu.space();
+                menu.print_text(ctx, font, "Find your way down to the dragon, and slay it.");
+            }
+            Screen::Playing => {}
+            Screen::Pause => {
+                menu.print_header_centered(ctx, font, "Paused");
+                menu.space();
+            }
+            Screen::Settings => {
+                menu.print_header_centered(ctx, font, "Settings");
+                menu.space();
+                menu.print_text(ctx, font, "Left and right: change, Enter: next value. Identification takes effect in the next game.");
+            }
+            Screen::GameOver => {
+                menu.print_header_centered(ctx, font, "Game over.");
+                menu.space();
+                menu.print_text(ctx, font, "Your adventure has ended in failure.");
+                menu.print_text(ctx, font, "Better luck next time!");
+            }
+            Screen::Victory => {
+                menu.print_header_centered(ctx, font, "Victory!");
+                menu.space();
+                menu.print_text(ctx, font, "You have defeated the dragon!");
+                menu.print_text(ctx, font, "Congratulations!");
+            }
+            Screen::QuitConfirm => {
+                menu.print_header_centered(ctx, font, "Quit?");
+                menu.space();
+                menu.print_text(ctx, font, "Any progress in the current game will be lost.");
+            }
+        }
+        menu.space();
+
+        for (i, item) in items.iter().enumerate() {
+            let selected = if i == cursor { "> " } else { "" };
+            let text = format!("{}{}", selected, item.label(settings));
+            menu.print(ctx, font, &text, 18.0, 10.0, false);
+            menu.y += 6.0;
+        }
+    }
+}
@@ -1,5 +1,7 @@
-use crate::world::entity::Entity;
-use crate::world::{attack_direction, entities, move_entity, split_entities};
+use crate::audio::Sound;
+use crate::world::context::{GameOptions, TurnContext};
+use crate::world::entity::{Entity, StatusKind};
+use crate::world::{attack_direction, cuts_corner, entities, move_entity, split_entities};
 
 #[derive(Debug, Clone)]
 pub enum Ai {
@@ -8,6 +10,8 @@ pub enum Ai {
     Zombie,
     Dragon,
     Flame,
+    FireVent,
+    Shopkeeper,
 }
 
 impl Ai {
@@ -17,43 +21,113 @@ impl Ai {
             Ai::Zombie => Box::new(ZombieAi::new()),
             Ai::Dragon => Box::new(DragonAi::new()),
             Ai::Flame => Box::new(FlameAi::new()),
+            Ai::FireVent => Box::new(FireVentAi::new()),
+            Ai::Shopkeeper => Box::new(ShopkeeperAi::new()),
         }
     }
 }
 
 pub trait AiTrait {
-    fn update(&mut self, index: usize, entities: &mut [Entity]) -> Option<Vec<Entity>>;
-    fn animation_state(&self, index: usize, entities: &[Entity]) -> (i32, bool);
+    fn update(
+        &mut self,
+        index: usize,
+        entities: &mut [Entity],
+        context: &mut TurnContext,
+    ) -> Option<Vec<Entity>>;
+    fn animation_state(
+        &self,
+        index: usize,
+        entities: &[Entity],
+        options: &GameOptions,
+    ) -> (i32, bool);
+    /// What the AI is up to, as shown in the look mode, if it's
+    /// anything worth mentioning.
+    fn state(&self, index: usize, entities: &[Entity]) -> Option<&'static str>;
+    /// Called when the player attacks the entity, whether or not the
+    /// attack hits.
+    fn provoke(&mut self, _context: &mut TurnContext) {}
 }
 
-fn find_player(ai_index: usize, entities: &[Entity]) -> Option<(i32, i32)> {
+/// Returns the direction of the player, if they're next to the AI.
+/// With `diagonal`, the diagonal neighbors count too, unless there's
+/// a wall corner in between.
+fn find_player(ai_index: usize, entities: &[Entity], diagonal: bool) -> Option<(i32, i32)> {
     let player = &entities[0].position;
     let me = &entities[ai_index].position;
     let (xd, yd) = (player.x - me.x, player.y - me.y);
-    if (xd == 0 && yd.abs() == 1) || (xd.abs() == 1 && yd == 0) {
+    let orthogonal_neighbor = (xd == 0 && yd.abs() == 1) || (xd.abs() == 1 && yd == 0);
+    let diagonal_neighbor = diagonal
+        && xd.abs() == 1
+        && yd.abs() == 1
+        && !entities.iter().any(|e| cuts_corner(me, xd, yd, e));
+    if orthogonal_neighbor || diagonal_neighbor {
         Some((xd, yd))
     } else {
         None
     }
 }
 
-fn find_path_to_player(ai_index: usize, entities: &[Entity], radius: i32) -> Option<(i32, i32)> {
+fn find_path_to_player(
+    ai_index: usize,
+    entities: &[Entity],
+    radius: i32,
+    diagonal: bool,
+) -> Option<(i32, i32)> {
+    find_path(ai_index, entities, radius, 1, diagonal)
+}
+
+fn find_path_away_from_player(
+    ai_index: usize,
+    entities: &[Entity],
+    radius: i32,
+    diagonal: bool,
+) -> Option<(i32, i32)> {
+    find_path(ai_index, entities, radius, -1, diagonal)
+}
+
+/// Finds a step towards (`sign` = 1) or away from (`sign` = -1) the
+/// player, avoiding the traps that have been revealed. With
+/// `diagonal`, a diagonal step is preferred when there's one to take.
+fn find_path(
+    ai_index: usize,
+    entities: &[Entity],
+    radius: i32,
+    sign: i32,
+    diagonal: bool,
+) -> Option<(i32, i32)> {
     let player = &entities[0].position;
     let me = &entities[ai_index].position;
     let (xd, yd) = (player.x - me.x, player.y - me.y);
     if xd.abs().max(yd.abs()) <= radius {
-        let xd_ = if xd == 0 { 0 } else { xd.abs() / xd };
-        let yd_ = if yd == 0 { 0 } else { yd.abs() / yd };
-        if xd.abs() > yd.abs() {
-            Some((xd_, 0))
+        let xd_ = if xd == 0 { 0 } else { xd.abs() / xd * sign };
+        let yd_ = if yd == 0 { 0 } else { yd.abs() / yd * sign };
+        let (primary, secondary) = if xd.abs() > yd.abs() {
+            ((xd_, 0), (0, yd_))
         } else {
-            Some((0, yd_))
-        }
+            ((0, yd_), (xd_, 0))
+        };
+        let diagonal_step = if diagonal { (xd_, yd_) } else { (0, 0) };
+        [diagonal_step, primary, secondary]
+            .iter()
+            .cloned()
+            .filter(|&(xd, yd)| xd != 0 || yd != 0)
+            .filter(|&(xd, yd)| !entities.iter().any(|e| cuts_corner(me, xd, yd, e)))
+            .find(|&(xd, yd)| !known_trap_at(entities, me.x + xd, me.y + yd))
     } else {
         None
     }
 }
 
+fn is_afraid(entity: &Entity) -> bool {
+    entity.has_status_effect(StatusKind::Fear)
+}
+
+fn known_trap_at(entities: &[Entity], x: i32, y: i32) -> bool {
+    entities
+        .iter()
+        .any(|e| e.position.x == x && e.position.y == y && e.trap.is_some() && !e.is_hidden())
+}
+
 const SKELETON_PATH: [(i32, i32); 8] = [
     (1, 0),
     (1, 0),
@@ -80,30 +154,29 @@ impl SkeletonAi {
             .health
             .iter()
             .any(|h| h.current <= h.max / 2)
+            || is_afraid(&entities[index])
     }
 }
 
 impl AiTrait for SkeletonAi {
-    fn update(&mut self, index: usize, entities: &mut [Entity]) -> Option<Vec<Entity>> {
+    fn update(
+        &mut self,
+        index: usize,
+        entities: &mut [Entity],
+        context: &mut TurnContext,
+    ) -> Option<Vec<Entity>> {
+        let diagonal = context.options.diagonal_movement;
         if self.scared(index, entities) {
-            let player_path = find_path_to_player(index, entities, 2);
-            if let Some((xd, yd)) = player_path {
+            let escape_path = find_path_away_from_player(index, entities, 2, diagonal);
+            if let Some((xd, yd)) = escape_path {
                 let (me, others) = split_entities(index, entities);
-                move_entity(&mut me.position, others, -xd, -yd);
+                move_entity(&mut me.position, others, xd, yd);
             }
         } else {
-            let player_direction = find_player(index, entities);
+            let player_direction = find_player(index, entities, diagonal);
             let (me, others) = split_entities(index, entities);
             if let Some((xd, yd)) = player_direction {
-                attack_direction(
-                    &me.position,
-                    me.damage.as_ref().unwrap(),
-                    &mut me.health,
-                    &me.inventory,
-                    others,
-                    xd,
-                    yd,
-                );
+                attack_direction(me, others, xd, yd, context);
             } else {
                 let (xd, yd) = SKELETON_PATH[self.step];
                 move_entity(&mut me.position, others, xd, yd);
@@ -113,15 +186,28 @@ impl AiTrait for SkeletonAi {
         None
     }
 
-    fn animation_state(&self, index: usize, entities: &[Entity]) -> (i32, bool) {
+    fn animation_state(
+        &self,
+        index: usize,
+        entities: &[Entity],
+        options: &GameOptions,
+    ) -> (i32, bool) {
         if self.scared(index, entities) {
             (2, false)
-        } else if find_player(index, entities).is_some() {
+        } else if find_player(index, entities, options.diagonal_movement).is_some() {
             (1, false)
         } else {
             (0, false)
         }
     }
+
+    fn state(&self, index: usize, entities: &[Entity]) -> Option<&'static str> {
+        if self.scared(index, entities) {
+            Some("Scared")
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -136,25 +222,32 @@ impl ZombieAi {
 }
 
 impl AiTrait for ZombieAi {
-    fn update(&mut self, index: usize, entities: &mut [Entity]) -> Option<Vec<Entity>> {
+    fn update(
+        &mut self,
+        index: usize,
+        entities: &mut [Entity],
+        context: &mut TurnContext,
+    ) -> Option<Vec<Entity>> {
+        let diagonal = context.options.diagonal_movement;
         if self.exhausted {
             self.exhausted = false;
             return None;
         }
 
-        let direction_attack = find_player(index, entities);
-        let direction_path = find_path_to_player(index, entities, 4);
+        if is_afraid(&entities[index]) {
+            if let Some((xd, yd)) = find_path_away_from_player(index, entities, 4, diagonal) {
+                let (me, others) = split_entities(index, entities);
+                move_entity(&mut me.position, others, xd, yd);
+            }
+            self.exhausted = true;
+            return None;
+        }
+
+        let direction_attack = find_player(index, entities, diagonal);
+        let direction_path = find_path_to_player(index, entities, 4, diagonal);
         let (me, others) = split_entities(index, entities);
         if let Some((xd, yd)) = direction_attack {
-            attack_direction(
-                &me.position,
-                me.damage.as_ref().unwrap(),
-                &mut me.health,
-                &me.inventory,
-                others,
-                xd,
-                yd,
-            );
+            attack_direction(me, others, xd, yd, context);
         } else {
             if let Some((xd, yd)) = direction_path {
                 move_entity(&mut me.position, others, xd, yd);
@@ -165,13 +258,26 @@ impl AiTrait for ZombieAi {
         None
     }
 
-    fn animation_state(&self, index: usize, entities: &[Entity]) -> (i32, bool) {
+    fn animation_state(
+        &self,
+        index: usize,
+        entities: &[Entity],
+        _options: &GameOptions,
+    ) -> (i32, bool) {
         if !self.exhausted || !entities[index].is_alive() {
             (1, true)
         } else {
             (0, false)
         }
     }
+
+    fn state(&self, _index: usize, _entities: &[Entity]) -> Option<&'static str> {
+        if self.exhausted {
+            Some("Exhausted")
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -190,7 +296,13 @@ impl DragonAi {
 }
 
 impl AiTrait for DragonAi {
-    fn update(&mut self, index: usize, entities: &mut [Entity]) -> Option<Vec<Entity>> {
+    fn update(
+        &mut self,
+        index: usize,
+        entities: &mut [Entity],
+        context: &mut TurnContext,
+    ) -> Option<Vec<Entity>> {
+        let diagonal = context.options.diagonal_movement;
         let mut spawns = None;
         if let Some((xd, yd, ref mut time)) = self.charge_direction {
             let (me, others) = split_entities(index, entities);
@@ -202,25 +314,22 @@ impl AiTrait for DragonAi {
             if !moved {
                 // Something was blocking the way, try to attack
                 let (me, others) = split_entities(index, entities);
-                attack_direction(
-                    &me.position,
-                    me.damage.as_ref().unwrap(),
-                    &mut me.health,
-                    &me.inventory,
-                    others,
-                    xd,
-                    yd,
-                );
+                attack_direction(me, others, xd, yd, context);
             }
         } else if let Some((ref mut flame, _)) = self.flame_stage {
             *flame += 1;
             if *flame == FLAME_LIFETIME - 1 {
                 self.flame_stage = None;
             }
+        } else if is_afraid(&entities[index]) {
+            if let Some((xd, yd)) = find_path_away_from_player(index, entities, 4, diagonal) {
+                let (me, others) = split_entities(index, entities);
+                move_entity(&mut me.position, others, xd, yd);
+            }
         } else {
             let mut next_strategy_chosen = false;
             if entities[index].health.iter().any(|h| h.current > h.max / 2) {
-                if let Some((xd, yd)) = find_path_to_player(index, entities, 4) {
+                if let Some((xd, yd)) = find_path_to_player(index, entities, 4, diagonal) {
                     self.charge_direction = Some((xd, yd, 5));
                     next_strategy_chosen = true;
                 }
@@ -247,13 +356,19 @@ impl AiTrait for DragonAi {
                         flames.push(entities::PROTO_FLAME.clone_at(x, y));
                     }
                 }
+                context.sounds.push(Sound::Flame);
                 spawns = Some(flames);
             }
         }
         spawns
     }
 
-    fn animation_state(&self, _index: usize, _entities: &[Entity]) -> (i32, bool) {
+    fn animation_state(
+        &self,
+        _index: usize,
+        _entities: &[Entity],
+        _options: &GameOptions,
+    ) -> (i32, bool) {
         if let Some((xd, yd, _)) = self.charge_direction {
             let flip = xd < 0 || yd < 0;
             (1, flip)
@@ -267,6 +382,16 @@ impl AiTrait for DragonAi {
             (0, false)
         }
     }
+
+    fn state(&self, _index: usize, _entities: &[Entity]) -> Option<&'static str> {
+        if self.charge_direction.is_some() {
+            Some("Charging")
+        } else if self.flame_stage.is_some() {
+            Some("Breathing fire")
+        } else {
+            None
+        }
+    }
 }
 
 const FLAME_BUILDUP_TICKS: i32 = 3;
@@ -284,19 +409,16 @@ impl FlameAi {
 }
 
 impl AiTrait for FlameAi {
-    fn update(&mut self, index: usize, entities: &mut [Entity]) -> Option<Vec<Entity>> {
+    fn update(
+        &mut self,
+        index: usize,
+        entities: &mut [Entity],
+        context: &mut TurnContext,
+    ) -> Option<Vec<Entity>> {
         self.state += 1;
         if self.state > FLAME_BUILDUP_TICKS && self.state < FLAME_LIFETIME - 1 {
             let (me, others) = split_entities(index, entities);
-            attack_direction(
-                &me.position,
-                me.damage.as_ref().unwrap(),
-                &mut me.health,
-                &me.inventory,
-                others,
-                0,
-                0,
-            );
+            attack_direction(me, others, 0, 0, context);
         }
         if self.state >= FLAME_LIFETIME {
             entities[index].marked_for_death = true;
@@ -304,7 +426,12 @@ impl AiTrait for FlameAi {
         None
     }
 
-    fn animation_state(&self, _index: usize, _entities: &[Entity]) -> (i32, bool) {
+    fn animation_state(
+        &self,
+        _index: usize,
+        _entities: &[Entity],
+        _options: &GameOptions,
+    ) -> (i32, bool) {
         let flip = (self.state - 1).max(FLAME_BUILDUP_TICKS) % 2 == 0;
         if self.state == FLAME_LIFETIME - 1 {
             (4, flip)
@@ -312,4 +439,132 @@ impl AiTrait for FlameAi {
             ((self.state - 1).min(FLAME_BUILDUP_TICKS), flip)
         }
     }
+
+    fn state(&self, _index: usize, _entities: &[Entity]) -> Option<&'static str> {
+        if self.state <= FLAME_BUILDUP_TICKS {
+            Some("Flaring up")
+        } else {
+            None
+        }
+    }
+}
+
+const FIRE_VENT_INTERVAL: i32 = 10;
+
+#[derive(Debug, Clone)]
+pub struct FireVentAi {
+    timer: i32,
+}
+
+impl FireVentAi {
+    const fn new() -> FireVentAi {
+        FireVentAi { timer: 0 }
+    }
+}
+
+impl AiTrait for FireVentAi {
+    fn update(
+        &mut self,
+        index: usize,
+        entities: &mut [Entity],
+        context: &mut TurnContext,
+    ) -> Option<Vec<Entity>> {
+        self.timer += 1;
+        if self.timer >= FIRE_VENT_INTERVAL {
+            self.timer = 0;
+            context.sounds.push(Sound::Flame);
+            let vent = &entities[index].position;
+            Some(vec![entities::PROTO_FLAME.clone_at(vent.x, vent.y)])
+        } else {
+            None
+        }
+    }
+
+    fn animation_state(
+        &self,
+        _index: usize,
+        _entities: &[Entity],
+        _options: &GameOptions,
+    ) -> (i32, bool) {
+        if self.timer == FIRE_VENT_INTERVAL - 1 {
+            (1, false)
+        } else {
+            (0, false)
+        }
+    }
+
+    fn state(&self, _index: usize, _entities: &[Entity]) -> Option<&'static str> {
+        if self.timer == FIRE_VENT_INTERVAL - 1 {
+            Some("About to erupt")
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ShopkeeperAi {
+    hostile: bool,
+}
+
+impl ShopkeeperAi {
+    const fn new() -> ShopkeeperAi {
+        ShopkeeperAi { hostile: false }
+    }
+}
+
+impl AiTrait for ShopkeeperAi {
+    fn update(
+        &mut self,
+        index: usize,
+        entities: &mut [Entity],
+        context: &mut TurnContext,
+    ) -> Option<Vec<Entity>> {
+        // Minds the shop until the player lays a hand on them
+        if !self.hostile {
+            return None;
+        }
+
+        let diagonal = context.options.diagonal_movement;
+
+        let direction_attack = find_player(index, entities, diagonal);
+        let direction_path = find_path_to_player(index, entities, 6, diagonal);
+        let (me, others) = split_entities(index, entities);
+        if let Some((xd, yd)) = direction_attack {
+            attack_direction(me, others, xd, yd, context);
+        } else if let Some((xd, yd)) = direction_path {
+            move_entity(&mut me.position, others, xd, yd);
+        }
+        None
+    }
+
+    fn animation_state(
+        &self,
+        _index: usize,
+        _entities: &[Entity],
+        _options: &GameOptions,
+    ) -> (i32, bool) {
+        if self.hostile {
+            (1, false)
+        } else {
+            (0, false)
+        }
+    }
+
+    fn state(&self, _index: usize, _entities: &[Entity]) -> Option<&'static str> {
+        if self.hostile {
+            Some("Hostile")
+        } else {
+            None
+        }
+    }
+
+    fn provoke(&mut self, context: &mut TurnContext) {
+        if !self.hostile {
+            self.hostile = true;
+            context
+                .log
+                .add(String::from("Shopkeeper: \"Thief! You'll pay for that!\""));
+        }
+    }
 }
@@ -0,0 +1,153 @@
+//! State that lives across turns but isn't part of any entity: the
+//! rules in play, the combat dice and the message log. Handed down
+//! to everything that acts during a turn.
+use super::entity::Entity;
+use crate::audio::Sound;
+use rand_core::RngCore;
+use rand_pcg::Pcg32;
+use std::collections::VecDeque;
+
+const MESSAGE_LOG_LENGTH: usize = 50;
+const MIN_HIT_CHANCE: i32 = 5;
+const MAX_HIT_CHANCE: i32 = 95;
+
+/// Options that change the rules of the game.
+#[derive(Debug, Clone, Default)]
+pub struct GameOptions {
+    /// Attacks roll to hit and to crit, instead of always hitting for
+    /// a fixed amount.
+    pub combat_rolls: bool,
+    /// Potions, scrolls and equipment have to be identified before
+    /// their names and effects are known.
+    pub identification: bool,
+    /// The player and the monsters can move and attack diagonally.
+    pub diagonal_movement: bool,
+}
+
+impl GameOptions {
+    /// Returns the options with everything that's on in either of
+    /// them turned on.
+    pub fn or(&self, other: &GameOptions) -> GameOptions {
+        GameOptions {
+            combat_rolls: self.combat_rolls || other.combat_rolls,
+            identification: self.identification || other.identification,
+            diagonal_movement: self.diagonal_movement || other.diagonal_movement,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MessageLog {
+    messages: VecDeque<String>,
+}
+
+impl MessageLog {
+    pub fn add(&mut self, message: String) {
+        if self.messages.len() >= MESSAGE_LOG_LENGTH {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(message);
+    }
+
+    /// Returns the last `count` messages, oldest first.
+    pub fn recent(&self, count: usize) -> impl Iterator<Item = &String> {
+        self.messages
+            .iter()
+            .skip(self.messages.len().saturating_sub(count))
+    }
+}
+
+/// A push waiting to be applied to whatever is standing on (`x`,
+/// `y`), see World::resolve_knockbacks.
+#[derive(Debug, Clone)]
+pub struct Knockback {
+    pub x: i32,
+    pub y: i32,
+    pub xd: i32,
+    pub yd: i32,
+    pub distance: i32,
+}
+
+/// The outcome of the dice rolled for an attack.
+#[derive(Debug, Clone)]
+pub struct AttackRoll {
+    pub hit: bool,
+    pub critical: bool,
+    /// What was rolled against what, for the message log. None if no
+    /// dice were rolled.
+    pub explanation: Option<String>,
+}
+
+impl AttackRoll {
+    fn certain_hit() -> AttackRoll {
+        AttackRoll {
+            hit: true,
+            critical: false,
+            explanation: None,
+        }
+    }
+}
+
+pub struct TurnContext {
+    pub options: GameOptions,
+    pub log: MessageLog,
+    /// Knockbacks caused by the attacks of the entity currently
+    /// acting. They're applied once it's done, as pushing needs
+    /// access to all the entities.
+    pub knockbacks: Vec<Knockback>,
+    /// Sounds to play when the animation gets to the step they
+    /// happened in. See World::record_animation_step.
+    pub sounds: Vec<Sound>,
+    /// Only used for the combat rolls, so that changes to combat
+    /// don't shift the sequences of the other random numbers.
+    combat_rng: Pcg32,
+}
+
+impl TurnContext {
+    pub fn new(combat_rng: Pcg32, options: GameOptions) -> TurnContext {
+        TurnContext {
+            options,
+            log: MessageLog::default(),
+            knockbacks: Vec::new(),
+            sounds: Vec::new(),
+            combat_rng,
+        }
+    }
+
+    /// Rolls whether the attack hits and whether it's a critical
+    /// hit. Without combat rolls, or if the attacker has no
+    /// CombatStats (traps, flames), every attack simply hits.
+    pub fn roll_attack(&mut self, attacker: &Entity, defender: &Entity) -> AttackRoll {
+        if !self.options.combat_rolls {
+            return AttackRoll::certain_hit();
+        }
+        let stats = match &attacker.combat_stats {
+            Some(stats) => stats,
+            None => return AttackRoll::certain_hit(),
+        };
+
+        let dodge = defender.combat_stats.as_ref().map(|s| s.dodge).unwrap_or(0);
+        let hit_chance = (stats.accuracy - dodge).clamp(MIN_HIT_CHANCE, MAX_HIT_CHANCE);
+        let hit_roll = self.roll_d100();
+        let hit = hit_roll <= hit_chance;
+        let mut explanation = format!("rolled {} of {} to hit", hit_roll, hit_chance);
+
+        let mut critical = false;
+        if hit && stats.critical > 0 {
+            let critical_roll = self.roll_d100();
+            critical = critical_roll <= stats.critical;
+            explanation += &format!(", {} of {} to crit", critical_roll, stats.critical);
+        }
+
+        AttackRoll {
+            hit,
+            critical,
+            explanation: Some(explanation),
+        }
+    }
+
+    /// Returns a number from 1 to 100.
+    fn roll_d100(&mut self) -> i32 {
+        (self.combat_rng.next_u32() % 100) as i32 + 1
+    }
+}
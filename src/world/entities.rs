@@ -1,6 +1,8 @@
 //! Prototypes of different kinds of entities.
 use super::ai::*;
 use super::entity::*;
+use super::experience::Experience;
+use super::loot;
 use crate::sprites;
 
 impl Animation {
@@ -26,12 +28,29 @@ impl Animation {
                 from: 0.0,
                 to: 0.0,
             },
+            events: PendingEvents::new(),
+            lunge: (0.0, 0.0),
+            hurt: false,
+            flash: 0.0,
+            spawn: Transition::Fade,
+            despawn: Transition::Fade,
+            despawned: false,
+        }
+    }
+
+    pub const fn with_transitions(spawn: Transition, despawn: Transition) -> Animation {
+        Animation {
+            spawn,
+            despawn,
+            ..Animation::default()
         }
     }
 }
 
 pub const PROTO_PLAYER: Entity = Entity {
     position: Position { x: 0, y: 0 },
+    name: "You",
+    description: "That's you. The way out is further down.",
     sprite: Sprite(sprites::PLAYER),
     visibility_affected: false,
     animation: Animation::default(),
@@ -41,36 +60,73 @@ pub const PROTO_PLAYER: Entity = Entity {
         max: 12,
     }),
     status_effects: Some(Vec::new()),
+    immunities: &[],
     damage: Some(Damage(2)),
-    inventory: Some(Inventory::new()),
+    defense: Some(Defense::new(0, 0)),
+    combat_stats: Some(CombatStats::new(90, 10, 10)),
+    inflicts: None,
+    knockback: 0,
+    inventory: Some(Inventory::new(4, 3)),
+    experience: Some(Experience::new()),
+    xp_value: 0,
+    gold_value: 0,
+    loot: None,
     ai: None,
     drop: None,
+    price: None,
     marked_for_death: false,
     door: false,
     next_level: false,
     dragon: false,
+    trap: None,
+    hidden_door: None,
 };
 
 pub const PROTO_WALL: Entity = Entity {
     position: Position { x: 0, y: 0 },
+    name: "Wall",
+    description: "Solid stone. Or so it seems.",
     sprite: Sprite(sprites::WALL),
     visibility_affected: false,
     animation: Animation::default(),
     denies_movement: true,
     health: None,
     status_effects: Some(Vec::new()),
+    immunities: &[],
     damage: None,
+    defense: None,
+    combat_stats: None,
+    inflicts: None,
+    knockback: 0,
     inventory: None,
+    experience: None,
+    xp_value: 0,
+    gold_value: 0,
+    loot: None,
     ai: None,
     drop: None,
+    price: None,
     marked_for_death: false,
     door: false,
     next_level: false,
     dragon: false,
+    trap: None,
+    hidden_door: None,
+};
+
+// Built from PROTO_WALL by assignment, since `..PROTO_WALL` would
+// drop the rest of the wall in a const, which isn't allowed because
+// of its status effect Vec.
+pub const PROTO_SECRET_WALL: Entity = {
+    let mut wall = PROTO_WALL;
+    wall.hidden_door = Some(3);
+    wall
 };
 
 pub const PROTO_SKELETON: Entity = Entity {
     position: Position { x: 0, y: 0 },
+    name: "Skeleton",
+    description: "Walks the same circle over and over, and runs off when it's hurt.",
     sprite: Sprite(sprites::SKELETON),
     visibility_affected: true,
     animation: Animation::default(),
@@ -80,37 +136,67 @@ pub const PROTO_SKELETON: Entity = Entity {
         max: 10,
     }),
     status_effects: Some(Vec::new()),
+    immunities: &[StatusKind::Bleed],
     damage: Some(Damage(2)),
+    defense: Some(Defense::new(0, 0)),
+    combat_stats: Some(CombatStats::new(80, 15, 5)),
+    inflicts: Some(StatusEffect::new(StatusKind::Weakness, 3)),
+    knockback: 0,
     inventory: None,
+    experience: None,
+    xp_value: 4,
+    gold_value: 3,
+    loot: Some(&loot::SKELETON_DROP),
     ai: Some(Ai::Skeleton),
     drop: None,
+    price: None,
     marked_for_death: false,
     door: false,
     next_level: false,
     dragon: false,
+    trap: None,
+    hidden_door: None,
 };
 
-#[allow(dead_code)]
 pub const PROTO_COBWEB: Entity = Entity {
     position: Position { x: 0, y: 0 },
+    name: "Cobweb",
+    description: "Sticky. Walking through it slows you down.",
     sprite: Sprite(sprites::COBWEB),
     visibility_affected: true,
     animation: Animation::default(),
     denies_movement: false,
     health: None,
     status_effects: None,
+    immunities: &[],
     damage: None,
+    defense: None,
+    combat_stats: None,
+    inflicts: None,
+    knockback: 0,
     inventory: None,
+    experience: None,
+    xp_value: 0,
+    gold_value: 0,
+    loot: None,
     ai: None,
     drop: None,
+    price: None,
     marked_for_death: false,
     door: false,
     next_level: false,
     dragon: false,
+    trap: Some(Trap {
+        kind: TrapKind::Cobweb,
+        hidden: false,
+    }),
+    hidden_door: None,
 };
 
 pub const PROTO_ZOMBIE: Entity = Entity {
     position: Position { x: 0, y: 0 },
+    name: "Zombie",
+    description: "Slow, but it never stops following you.",
     sprite: Sprite(sprites::ZOMBIE),
     visibility_affected: true,
     animation: Animation::default(),
@@ -120,18 +206,32 @@ pub const PROTO_ZOMBIE: Entity = Entity {
         max: 12,
     }),
     status_effects: Some(Vec::new()),
+    immunities: &[],
     damage: Some(Damage(2)),
+    defense: Some(Defense::new(0, 25)),
+    combat_stats: Some(CombatStats::new(75, 0, 5)),
+    inflicts: None,
+    knockback: 0,
     inventory: None,
+    experience: None,
+    xp_value: 5,
+    gold_value: 4,
+    loot: Some(&loot::ZOMBIE_DROP),
     ai: Some(Ai::Zombie),
     drop: None,
+    price: None,
     marked_for_death: false,
     door: false,
     next_level: false,
     dragon: false,
+    trap: None,
+    hidden_door: None,
 };
 
 pub const PROTO_DRAGON: Entity = Entity {
     position: Position { x: 0, y: 0 },
+    name: "Dragon",
+    description: "Charges across the room and breathes fire. Best not stand in its way.",
     sprite: Sprite(sprites::DRAGON),
     visibility_affected: true,
     animation: Animation::default(),
@@ -141,86 +241,251 @@ pub const PROTO_DRAGON: Entity = Entity {
         max: 4 * 9,
     }),
     status_effects: Some(Vec::new()),
+    immunities: &[StatusKind::Burn],
     damage: Some(Damage(5)),
+    defense: Some(Defense::new(1, 0)),
+    combat_stats: Some(CombatStats::new(85, 5, 10)),
+    inflicts: None,
+    knockback: 2,
     inventory: None,
+    experience: None,
+    xp_value: 50,
+    gold_value: 0,
+    loot: None,
     ai: Some(Ai::Dragon),
     drop: None,
+    price: None,
     marked_for_death: false,
     door: false,
     next_level: false,
     dragon: true,
+    trap: None,
+    hidden_door: None,
+};
+
+pub const PROTO_SHOPKEEPER: Entity = Entity {
+    position: Position { x: 0, y: 0 },
+    name: "Shopkeeper",
+    description: "Sells wares for gold. Doesn't take kindly to thieves.",
+    sprite: Sprite(sprites::SHOPKEEPER),
+    visibility_affected: true,
+    animation: Animation::default(),
+    denies_movement: true,
+    health: Some(Health {
+        current: 20,
+        max: 20,
+    }),
+    status_effects: Some(Vec::new()),
+    immunities: &[],
+    damage: Some(Damage(3)),
+    defense: Some(Defense::new(1, 0)),
+    combat_stats: Some(CombatStats::new(90, 10, 5)),
+    inflicts: None,
+    knockback: 0,
+    inventory: None,
+    experience: None,
+    xp_value: 15,
+    gold_value: 20,
+    loot: Some(&loot::SHOPKEEPER_DROP),
+    ai: Some(Ai::Shopkeeper),
+    drop: None,
+    price: None,
+    marked_for_death: false,
+    door: false,
+    next_level: false,
+    dragon: false,
+    trap: None,
+    hidden_door: None,
 };
 
 pub const PROTO_FLAME: Entity = Entity {
     position: Position { x: 0, y: 0 },
+    name: "Flame",
+    description: "Burns whatever stands in it.",
     sprite: Sprite(sprites::FLAME),
     visibility_affected: true,
-    animation: Animation::default(),
+    animation: Animation::with_transitions(Transition::Scale, Transition::Fade),
     denies_movement: false,
     health: None,
     status_effects: None,
+    immunities: &[],
     damage: Some(Damage(3)),
+    defense: None,
+    combat_stats: None,
+    inflicts: Some(StatusEffect::new(StatusKind::Burn, 2)),
+    knockback: 0,
     inventory: None,
+    experience: None,
+    xp_value: 0,
+    gold_value: 0,
+    loot: None,
     ai: Some(Ai::Flame),
     drop: None,
+    price: None,
     marked_for_death: false,
     door: false,
     next_level: false,
     dragon: false,
+    trap: None,
+    hidden_door: None,
 };
 
 pub const PROTO_DOOR: Entity = Entity {
     position: Position { x: 0, y: 0 },
+    name: "Door",
+    description: "Opens when you walk into it.",
     sprite: Sprite(sprites::DOOR),
     visibility_affected: false,
-    animation: Animation::default(),
+    animation: Animation::with_transitions(Transition::Fade, Transition::Swing(sprites::DOOR_OPEN)),
     denies_movement: true,
     health: None,
     status_effects: None,
+    immunities: &[],
     damage: None,
+    defense: None,
+    combat_stats: None,
+    inflicts: None,
+    knockback: 0,
     inventory: None,
+    experience: None,
+    xp_value: 0,
+    gold_value: 0,
+    loot: None,
     ai: None,
     drop: None,
+    price: None,
     marked_for_death: false,
     door: true,
     next_level: false,
     dragon: false,
+    trap: None,
+    hidden_door: None,
 };
 
 pub const PROTO_NEXT_LEVEL: Entity = Entity {
     position: Position { x: 0, y: 0 },
+    name: "Hole",
+    description: "Leads down to the next level. There's no climbing back up.",
     sprite: Sprite(sprites::NEXT_LEVEL),
     visibility_affected: true,
     animation: Animation::default(),
     denies_movement: false,
     health: None,
     status_effects: None,
+    immunities: &[],
     damage: None,
+    defense: None,
+    combat_stats: None,
+    inflicts: None,
+    knockback: 0,
     inventory: None,
+    experience: None,
+    xp_value: 0,
+    gold_value: 0,
+    loot: None,
     ai: None,
     drop: None,
+    price: None,
     marked_for_death: false,
     door: false,
     next_level: true,
     dragon: false,
+    trap: None,
+    hidden_door: None,
+};
+
+pub const PROTO_SPIKES: Entity = Entity {
+    position: Position { x: 0, y: 0 },
+    name: "Spikes",
+    description: "Stepping on them hurts, and leaves you bleeding.",
+    sprite: Sprite(sprites::TRAP_SPIKES),
+    visibility_affected: true,
+    animation: Animation::default(),
+    denies_movement: false,
+    health: None,
+    status_effects: None,
+    immunities: &[],
+    damage: Some(Damage(3)),
+    defense: None,
+    combat_stats: None,
+    inflicts: Some(StatusEffect::new(StatusKind::Bleed, 3)),
+    knockback: 0,
+    inventory: None,
+    experience: None,
+    xp_value: 0,
+    gold_value: 0,
+    loot: None,
+    ai: None,
+    drop: None,
+    price: None,
+    marked_for_death: false,
+    door: false,
+    next_level: false,
+    dragon: false,
+    trap: Some(Trap {
+        kind: TrapKind::Spikes,
+        hidden: false,
+    }),
+    hidden_door: None,
+};
+
+pub const PROTO_PRESSURE_PLATE: Entity = Entity {
+    name: "Pressure plate",
+    description: "Something happens when it's stepped on. Once.",
+    sprite: Sprite(sprites::TRAP_PRESSURE_PLATE),
+    damage: None,
+    inflicts: None,
+    trap: Some(Trap {
+        kind: TrapKind::PressurePlate,
+        hidden: false,
+    }),
+    ..PROTO_SPIKES
+};
+
+pub const PROTO_FIRE_VENT: Entity = Entity {
+    name: "Fire vent",
+    description: "Spews flames every now and then.",
+    sprite: Sprite(sprites::TRAP_FIRE_VENT),
+    damage: None,
+    inflicts: None,
+    ai: Some(Ai::FireVent),
+    trap: Some(Trap {
+        kind: TrapKind::FireVent,
+        hidden: false,
+    }),
+    ..PROTO_SPIKES
 };
 
 const PROTO_ITEM: Entity = Entity {
     position: Position { x: 0, y: 0 },
+    name: "Item",
+    description: "An item.",
     sprite: Sprite(sprites::ITEM_SWORD),
     visibility_affected: true,
     animation: Animation::default(),
     denies_movement: false,
     health: None,
     status_effects: None,
+    immunities: &[],
     damage: None,
+    defense: None,
+    combat_stats: None,
+    inflicts: None,
+    knockback: 0,
     inventory: None,
+    experience: None,
+    xp_value: 0,
+    gold_value: 0,
+    loot: None,
     ai: None,
     drop: None,
+    price: None,
     marked_for_death: false,
     door: false,
     next_level: false,
     dragon: false,
+    trap: None,
+    hidden_door: None,
 };
 
 pub const PROTO_APPLE: Entity = Entity {
@@ -229,40 +494,21 @@ pub const PROTO_APPLE: Entity = Entity {
     ..PROTO_ITEM
 };
 
-pub const PROTO_ITEMS: [Entity; 7] = [
+/// Creates a pickup entity for a ware sold in a shop.
+pub fn ware_entity(item: Item, price: i32) -> Entity {
     Entity {
-        sprite: Sprite(sprites::ITEM_SWORD),
-        drop: Some(Item::Sword),
-        ..PROTO_ITEM
-    },
-    Entity {
-        sprite: Sprite(sprites::ITEM_SHIELD),
-        drop: Some(Item::Shield),
-        ..PROTO_ITEM
-    },
-    Entity {
-        sprite: Sprite(sprites::ITEM_HAMMER),
-        drop: Some(Item::Hammer),
-        ..PROTO_ITEM
-    },
-    Entity {
-        sprite: Sprite(sprites::ITEM_DAGGER),
-        drop: Some(Item::Dagger),
-        ..PROTO_ITEM
-    },
-    Entity {
-        sprite: Sprite(sprites::ITEM_VAMPIRE_TEETH),
-        drop: Some(Item::VampireTeeth),
-        ..PROTO_ITEM
-    },
-    Entity {
-        sprite: Sprite(sprites::ITEM_SCYTHE),
-        drop: Some(Item::Scythe),
-        ..PROTO_ITEM
-    },
+        price: Some(price),
+        ..pickup_entity(item)
+    }
+}
+
+/// Creates a pickup entity for the item.
+pub fn pickup_entity(item: Item) -> Entity {
     Entity {
-        sprite: Sprite(sprites::ITEM_STOPWATCH),
-        drop: Some(Item::Stopwatch(false)),
+        name: item.name(),
+        description: item.description(),
+        sprite: item.sprite(),
+        drop: Some(item),
         ..PROTO_ITEM
-    },
-];
+    }
+}
@@ -1,25 +1,56 @@
 use crate::sprites::{self, SpriteData};
 use crate::world::ai::Ai;
-use std::iter::Chain;
-use std::option::IterMut;
+use crate::world::experience::Experience;
+use crate::world::loot::LootTable;
+use crate::world::status::Stacking;
+pub use crate::world::status::{StatusEffect, StatusKind};
+use std::slice::IterMut;
 
 #[derive(Debug, Clone)]
 pub struct Entity {
     pub position: Position,
+    pub name: &'static str,
+    /// Shown in the look mode.
+    pub description: &'static str,
     pub sprite: Sprite,
     pub visibility_affected: bool,
     pub animation: Animation,
     pub denies_movement: bool,
     pub health: Option<Health>,
     pub status_effects: Option<Vec<StatusEffect>>,
+    /// Status effects that can never be applied to this entity.
+    pub immunities: &'static [StatusKind],
     pub damage: Option<Damage>,
+    /// The entity's natural armor, before any items.
+    pub defense: Option<Defense>,
+    /// Used for the combat rolls, if they're enabled.
+    pub combat_stats: Option<CombatStats>,
+    /// Applied to everything this entity hits.
+    pub inflicts: Option<StatusEffect>,
+    /// How many tiles this entity's attacks push the target back, on
+    /// top of what its items do.
+    pub knockback: i32,
     pub inventory: Option<Inventory>,
+    pub experience: Option<Experience>,
+    /// The XP the player gets when this entity dies.
+    pub xp_value: i32,
+    /// The most gold this entity drops when it dies.
+    pub gold_value: i32,
+    /// Rolled for an item to drop when this entity dies.
+    pub loot: Option<&'static LootTable>,
     pub ai: Option<Ai>,
     pub drop: Option<Item>,
+    /// Wares in a shop cost this much gold, and can't be picked up
+    /// before they're bought.
+    pub price: Option<i32>,
     pub marked_for_death: bool,
     pub door: bool,
     pub next_level: bool,
     pub dragon: bool,
+    pub trap: Option<Trap>,
+    /// Walls that are secretly doors. The amount is how many more
+    /// bumps or searches it takes for the player to notice.
+    pub hidden_door: Option<i32>,
 }
 
 impl Entity {
@@ -29,6 +60,29 @@ impl Entity {
         new_entity
     }
 
+    /// The damage the entity's attacks deal before crits and the
+    /// defender's defense: the base Damage, modified by the status
+    /// effects and then the carried items.
+    pub fn effective_damage(&self) -> i32 {
+        let mut damage = self.damage.as_ref().map(|damage| damage.0).unwrap_or(0);
+        for effect in self.status_effects.iter().flatten() {
+            damage = effect.kind.behavior().modify_damage(damage, effect);
+        }
+        if let Some(inventory) = &self.inventory {
+            damage = inventory.damage_after_items(damage);
+        }
+        damage
+    }
+
+    /// The entity's defense, including the items it carries.
+    pub fn effective_defense(&self) -> Defense {
+        let natural = self.defense.unwrap_or_else(|| Defense::new(0, 0));
+        match &self.inventory {
+            Some(inventory) => natural.combine(inventory.defense_from_items()),
+            None => natural,
+        }
+    }
+
     pub fn is_alive(&self) -> bool {
         if let Some(health) = &self.health {
             health.current > 0
@@ -39,46 +93,129 @@ impl Entity {
         }
     }
 
+    /// Hidden traps shouldn't be drawn or avoided until they've been
+    /// found.
+    pub fn is_hidden(&self) -> bool {
+        self.trap.iter().any(|trap| trap.hidden)
+    }
+
+    /// Searches (or bumps into) this entity, turning it into a door
+    /// if it's been searched enough.
+    pub fn search(&mut self) {
+        if let Some(searches_left) = &mut self.hidden_door {
+            *searches_left -= 1;
+            if *searches_left <= 0 {
+                self.hidden_door = None;
+                self.door = true;
+                self.sprite = Sprite(sprites::DOOR);
+                self.animation.despawn = Transition::Swing(sprites::DOOR_OPEN);
+            }
+        }
+    }
+
     pub fn visibility_affected(&self) -> bool {
         self.is_alive() && self.visibility_affected
     }
 
-    pub fn can_act(&self) -> bool {
-        let stunned = if let Some(status_effects) = &self.status_effects {
-            status_effects.contains(&StatusEffect::Stun)
-        } else {
-            false
-        };
-        self.is_alive() && !stunned && !self.marked_for_death
+    pub fn status_effect(&self, kind: StatusKind) -> Option<&StatusEffect> {
+        self.status_effects
+            .iter()
+            .flatten()
+            .find(|effect| effect.kind == kind)
     }
 
-    pub fn tick_status_effects(&mut self) {
-        if let Some(status_effects) = &mut self.status_effects {
-            let mut stunned = false;
-            for status_effect in status_effects.iter_mut() {
-                match status_effect {
-                    StatusEffect::Stun => {
-                        stunned = true;
-                    }
-                    StatusEffect::StunImmunity => {}
-                    StatusEffect::Poison { stacks, duration } => {
-                        if let Some(health) = &mut self.health {
-                            health.current = (health.current - *stacks).max(0);
-                        }
-                        *duration -= 1;
-                    }
+    pub fn has_status_effect(&self, kind: StatusKind) -> bool {
+        self.status_effect(kind).is_some()
+    }
+
+    /// Returns true if the entity can't be affected by `kind`, either
+    /// by nature or because of another status effect.
+    pub fn is_immune(&self, kind: StatusKind) -> bool {
+        self.immunities.contains(&kind)
+            || self
+                .status_effects
+                .iter()
+                .flatten()
+                .any(|effect| effect.kind.behavior().immunities().contains(&kind))
+    }
+
+    /// Applies the status effect, or if there's one of the same kind
+    /// already, stacks them according to the kind's Stacking.
+    pub fn apply_status_effect(&mut self, status_effect: StatusEffect) {
+        if self.is_immune(status_effect.kind) {
+            return;
+        }
+        let behavior = status_effect.kind.behavior();
+        let status_effects = match &mut self.status_effects {
+            Some(status_effects) => status_effects,
+            None => return,
+        };
+        if let Some(existing) = status_effects
+            .iter_mut()
+            .find(|effect| effect.kind == status_effect.kind)
+        {
+            match behavior.stacking() {
+                Stacking::Refresh => {
+                    existing.duration = existing.duration.max(status_effect.duration);
+                }
+                Stacking::Stack { max } => {
+                    existing.stacks = (existing.stacks + status_effect.stacks).min(max);
+                    existing.duration = existing.duration.max(status_effect.duration);
                 }
+                Stacking::Extend => existing.duration += status_effect.duration,
             }
-            status_effects.retain(|status_effect| match status_effect {
-                StatusEffect::Stun => false,
-                StatusEffect::StunImmunity => false,
-                StatusEffect::Poison { duration, .. } => *duration > 0,
-            });
-            if stunned {
-                status_effects.push(StatusEffect::StunImmunity);
+        } else {
+            status_effects.push(status_effect);
+            behavior.on_apply(self, &status_effect);
+        }
+    }
+
+    pub fn remove_status_effect(&mut self, kind: StatusKind) {
+        if let Some(status_effects) = &mut self.status_effects {
+            status_effects.retain(|effect| effect.kind != kind);
+        }
+    }
+
+    pub fn can_act(&self) -> bool {
+        let prevented = self
+            .status_effects
+            .iter()
+            .flatten()
+            .any(|effect| effect.kind.behavior().prevents_action(effect));
+        self.is_alive() && !prevented && !self.marked_for_death
+    }
+
+    /// Heals the entity, making a note of it for the animation.
+    pub fn heal(&mut self, amount: i32) {
+        if let Some(health) = &mut self.health {
+            let previous = health.current;
+            health.heal(amount);
+            let healed = health.current - previous;
+            if healed > 0 {
+                self.animation.events.push(CombatEvent::Heal(healed));
             }
         }
     }
+
+    /// Runs the status effects' ticks, counts down their durations,
+    /// and removes the ones that have run out.
+    pub fn tick_status_effects(&mut self) {
+        let mut status_effects = match self.status_effects.take() {
+            Some(status_effects) => status_effects,
+            None => return,
+        };
+        for effect in &mut status_effects {
+            effect.kind.behavior().on_tick(self, effect);
+            effect.duration -= 1;
+        }
+        let (expired, active) = status_effects
+            .into_iter()
+            .partition::<Vec<StatusEffect>, _>(|effect| effect.duration <= 0);
+        self.status_effects = Some(active);
+        for effect in expired {
+            effect.kind.behavior().on_expire(self, &effect);
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -96,6 +233,137 @@ pub struct Animation {
     pub y: AnimationState<f32>,
     pub opacity: AnimationState<f32>,
     pub rotation: AnimationState<f32>,
+    /// Things that happened to the entity this round, which haven't
+    /// been attached to a step of the round's animation yet.
+    pub events: PendingEvents,
+    /// The direction the entity is lunging in, if it attacks during
+    /// the step being animated.
+    pub lunge: (f32, f32),
+    /// Whether the entity gets hurt during the step being animated.
+    pub hurt: bool,
+    /// How red the entity is flashing from being hurt, 0.0 - 1.0.
+    pub flash: f32,
+    /// How the entity appears when it's spawned in the middle of a
+    /// round.
+    pub spawn: Transition,
+    /// How the entity disappears when it's marked for death.
+    pub despawn: Transition,
+    /// Whether the entity's despawn transition has been queued up
+    /// already.
+    pub despawned: bool,
+}
+
+/// The ways entities can appear and disappear.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transition {
+    Fade,
+    /// Grows from nothing, or shrinks away into nothing.
+    Scale,
+    /// Switches to the sprite, and then fades out. Doors swing open
+    /// like this.
+    Swing(SpriteData),
+}
+
+impl Transition {
+    /// How the entity is drawn when it's `presence` of the way (0.0 -
+    /// 1.0) into existence. Spawning goes from 0.0 to 1.0, despawning
+    /// from 1.0 to 0.0.
+    pub fn frame(self, presence: f32) -> TransitionFrame {
+        let (sprite, scale, opacity) = match self {
+            _ if presence >= 1.0 => (None, 1.0, 1.0),
+            Transition::Fade => (None, 1.0, presence),
+            Transition::Scale => (None, presence, 1.0),
+            Transition::Swing(sprite) => (Some(sprite), 1.0, (presence * 2.0).min(1.0)),
+        };
+        TransitionFrame {
+            sprite,
+            scale,
+            opacity,
+        }
+    }
+}
+
+/// How an entity in the middle of a transition is drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransitionFrame {
+    /// The sprite to draw instead of the entity's own.
+    pub sprite: Option<SpriteData>,
+    pub scale: f32,
+    pub opacity: f32,
+}
+
+impl TransitionFrame {
+    pub const PRESENT: TransitionFrame = TransitionFrame {
+        sprite: None,
+        scale: 1.0,
+        opacity: 1.0,
+    };
+}
+
+/// Something that happened to an entity during a round, played back
+/// when the step of the round it happened in is animated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CombatEvent {
+    /// Attacked the tile in the direction.
+    Attack(i32, i32),
+    Damage(i32),
+    /// Damage from poison, shown in green.
+    Poison(i32),
+    Heal(i32),
+}
+
+/// The combat events of an entity that haven't been attached to an
+/// animation step yet. The amounts are summed up instead of kept in a
+/// list, so that entities can still be put together in consts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PendingEvents {
+    attack: Option<(i32, i32)>,
+    damage: i32,
+    poison: i32,
+    heal: i32,
+}
+
+impl PendingEvents {
+    pub const fn new() -> PendingEvents {
+        PendingEvents {
+            attack: None,
+            damage: 0,
+            poison: 0,
+            heal: 0,
+        }
+    }
+
+    pub fn push(&mut self, event: CombatEvent) {
+        match event {
+            CombatEvent::Attack(xd, yd) => self.attack = Some((xd, yd)),
+            CombatEvent::Damage(amount) => self.damage += amount,
+            CombatEvent::Poison(amount) => self.poison += amount,
+            CombatEvent::Heal(amount) => self.heal += amount,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == PendingEvents::new()
+    }
+
+    /// Returns the pending events, and clears them.
+    pub fn take(&mut self) -> Vec<CombatEvent> {
+        let mut events = Vec::new();
+        if let Some((xd, yd)) = self.attack {
+            events.push(CombatEvent::Attack(xd, yd));
+        }
+        if self.damage > 0 {
+            events.push(CombatEvent::Damage(self.damage));
+        }
+        if self.poison > 0 {
+            events.push(CombatEvent::Poison(self.poison));
+        }
+        if self.heal > 0 {
+            events.push(CombatEvent::Heal(self.heal));
+        }
+        *self = PendingEvents::new();
+        events
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -111,84 +379,265 @@ pub struct Health {
     pub max: i32,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum StatusEffect {
-    Stun,
-    StunImmunity,
-    Poison { stacks: i32, duration: i32 },
+impl Health {
+    pub fn heal(&mut self, amount: i32) {
+        self.current = (self.current + amount).min(self.max);
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Damage(pub i32);
 
-#[derive(Debug, Clone)]
-pub struct Inventory {
-    pub item_left: Option<Item>,
-    pub item_right: Option<Item>,
-    older_item: Option<ItemIndex>,
+/// Percentage chances for the combat rolls, see
+/// TurnContext::roll_attack.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CombatStats {
+    pub accuracy: i32,
+    pub dodge: i32,
+    pub critical: i32,
+}
+
+impl CombatStats {
+    pub const fn new(accuracy: i32, dodge: i32, critical: i32) -> CombatStats {
+        CombatStats {
+            accuracy,
+            dodge,
+            critical,
+        }
+    }
+}
+
+/// Reduces incoming damage, see calculate_damage for how.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Defense {
+    /// Subtracted from every hit.
+    pub flat: i32,
+    /// The percentage of the remaining damage that is blocked.
+    pub percent: i32,
+}
+
+impl Defense {
+    pub const MAX_PERCENT: i32 = 75;
+
+    pub const fn new(flat: i32, percent: i32) -> Defense {
+        Defense { flat, percent }
+    }
+
+    /// Adds up the two defenses. Percentages stack additively, up to
+    /// MAX_PERCENT.
+    pub fn combine(self, other: Defense) -> Defense {
+        Defense {
+            flat: self.flat + other.flat,
+            percent: (self.percent + other.percent).min(Defense::MAX_PERCENT),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trap {
+    pub kind: TrapKind,
+    /// Hidden traps are revealed when the player walks next to them,
+    /// or when something springs them.
+    pub hidden: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrapKind {
+    /// Damages whatever steps on it.
+    Spikes,
+    /// Sets the surrounding tiles ablaze when stepped on, and is used
+    /// up doing so.
+    PressurePlate,
+    /// Spits out a flame every now and then, regardless of who's
+    /// standing on it.
+    FireVent,
+    /// Slows down whatever walks into it, and gets torn apart.
+    Cobweb,
 }
 
 #[derive(Debug, Clone)]
-enum ItemIndex {
-    Left,
-    Right,
+pub struct Inventory {
+    pub items: Vec<Item>,
+    /// How many items fit in the inventory.
+    pub slots: usize,
+    /// Consumables are kept separate from the rest of the items, so
+    /// they don't compete for space.
+    pub belt: Vec<Consumable>,
+    pub belt_slots: usize,
+    pub gold: i32,
 }
 
 impl Inventory {
-    pub const fn new() -> Inventory {
+    pub const fn new(slots: usize, belt_slots: usize) -> Inventory {
         Inventory {
-            item_left: None,
-            item_right: None,
-            older_item: None,
+            items: Vec::new(),
+            slots,
+            belt: Vec::new(),
+            belt_slots,
+            gold: 0,
         }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.item_left.is_none() && self.item_right.is_none()
+        self.items.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.items.len() >= self.slots
     }
 
     pub fn has_item(&self, item: Item) -> bool {
-        self.item_left.iter().any(|i| *i == item) || self.item_right.iter().any(|i| *i == item)
+        self.items.contains(&item)
+    }
+
+    /// The damage after every item's ItemEffect::modify_damage,
+    /// applied in the order of their ItemEffect::priority.
+    pub fn damage_after_items(&self, damage: i32) -> i32 {
+        let mut items = self.items.clone();
+        items.sort_by_key(|item| item.effect().priority());
+        items
+            .iter()
+            .fold(damage, |damage, item| item.effect().modify_damage(damage))
     }
 
-    pub fn damage_after_items(&self, mut damage: i32) -> i32 {
-        if self.has_item(Item::Sword) {
-            damage *= 2;
+    /// The defense granted by all the carried items combined.
+    pub fn defense_from_items(&self) -> Defense {
+        self.items.iter().fold(Defense::new(0, 0), |defense, item| {
+            defense.combine(item.defense())
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, Item> {
+        self.items.iter_mut()
+    }
+
+    /// Adds the item into a free slot. Returns false if there's no
+    /// room.
+    pub fn add_item(&mut self, item: Item) -> bool {
+        if self.is_full() {
+            false
+        } else {
+            self.items.push(item);
+            true
         }
-        if self.has_item(Item::Dagger) {
-            damage /= 2;
+    }
+
+    /// Puts the item in the given slot, returning the item that was
+    /// there before.
+    pub fn replace_item(&mut self, slot: usize, item: Item) -> Option<Item> {
+        if slot < self.items.len() {
+            Some(std::mem::replace(&mut self.items[slot], item))
+        } else {
+            self.items.push(item);
+            None
         }
-        damage
     }
 
-    pub fn iter_mut(&mut self) -> Chain<IterMut<Item>, IterMut<Item>> {
-        self.item_left.iter_mut().chain(self.item_right.iter_mut())
+    pub fn remove_item(&mut self, slot: usize) -> Option<Item> {
+        if slot < self.items.len() {
+            Some(self.items.remove(slot))
+        } else {
+            None
+        }
     }
 
-    pub fn add_item(&mut self, item: Item) -> Option<Item> {
-        if let Some(older_item) = &mut self.older_item {
-            match older_item {
-                ItemIndex::Left => {
-                    self.older_item = Some(ItemIndex::Right);
-                    let thrown_out = self.item_left;
-                    self.item_left = Some(item);
-                    thrown_out
-                }
-                ItemIndex::Right => {
-                    self.older_item = Some(ItemIndex::Left);
-                    let thrown_out = self.item_right;
-                    self.item_right = Some(item);
-                    thrown_out
-                }
-            }
+    /// Puts the consumable on the belt. Returns false if there's no
+    /// room.
+    pub fn add_consumable(&mut self, consumable: Consumable) -> bool {
+        if self.belt.len() >= self.belt_slots {
+            false
+        } else {
+            self.belt.push(consumable);
+            true
+        }
+    }
+
+    pub fn take_consumable(&mut self, slot: usize) -> Option<Consumable> {
+        if slot < self.belt.len() {
+            Some(self.belt.remove(slot))
         } else {
-            self.older_item = Some(ItemIndex::Right);
-            self.item_left = Some(item);
             None
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Consumable {
+    HealingPotion,
+    HastePotion,
+    StrengthPotion,
+    TeleportScroll,
+    MappingScroll,
+    FearScroll,
+    ThrowingKnife,
+    RegenerationPotion,
+    IdentifyScroll,
+}
+
+impl Consumable {
+    /// Throwables aren't used up like the rest, they're thrown at
+    /// things instead.
+    pub fn is_throwable(&self) -> bool {
+        *self == Consumable::ThrowingKnife
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Consumable::HealingPotion => "Healing potion",
+            Consumable::HastePotion => "Haste potion",
+            Consumable::StrengthPotion => "Strength potion",
+            Consumable::TeleportScroll => "Scroll of teleport",
+            Consumable::MappingScroll => "Scroll of mapping",
+            Consumable::FearScroll => "Scroll of fear",
+            Consumable::ThrowingKnife => "Throwing knife",
+            Consumable::RegenerationPotion => "Regeneration potion",
+            Consumable::IdentifyScroll => "Scroll of identify",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Consumable::HealingPotion => {
+                "Smells like medicine, tastes like cherries. Heals 8 health."
+            }
+            Consumable::HastePotion => "Fizzy! For 10 turns, enemies only move every other turn.",
+            Consumable::StrengthPotion => {
+                "Liquid courage. Adds 2 to your base damage for 10 turns."
+            }
+            Consumable::TeleportScroll => {
+                "The letters shift around when you're not looking. Teleports you to a random room."
+            }
+            Consumable::MappingScroll => "A map of this very level, traps and secrets included.",
+            Consumable::FearScroll => {
+                "A drawing too horrible to describe. Scares the monsters in the room for 8 turns."
+            }
+            Consumable::ThrowingKnife => {
+                "Perfectly balanced, for throwing at things from afar. Deals 3 damage."
+            }
+            Consumable::RegenerationPotion => {
+                "Thick and green. Cures poison and bleeding, and heals 1 health every turn for 12 turns."
+            }
+            Consumable::IdentifyScroll => {
+                "Reveals the true nature of everything you carry, and everything lying around in the room."
+            }
+        }
+    }
+
+    pub fn sprite(&self) -> Sprite {
+        match self {
+            Consumable::HealingPotion => Sprite(sprites::POTION_HEALING),
+            Consumable::HastePotion => Sprite(sprites::POTION_HASTE),
+            Consumable::StrengthPotion => Sprite(sprites::POTION_STRENGTH),
+            Consumable::TeleportScroll => Sprite(sprites::SCROLL_TELEPORT),
+            Consumable::MappingScroll => Sprite(sprites::SCROLL_MAPPING),
+            Consumable::FearScroll => Sprite(sprites::SCROLL_FEAR),
+            Consumable::ThrowingKnife => Sprite(sprites::THROWING_KNIFE),
+            Consumable::RegenerationPotion => Sprite(sprites::POTION_REGENERATION),
+            Consumable::IdentifyScroll => Sprite(sprites::SCROLL_IDENTIFY),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Item {
     Sword,
@@ -196,54 +645,37 @@ pub enum Item {
     Hammer,
     Dagger,
     Shield,
+    LeatherArmor,
+    ChainMail,
     VampireTeeth,
     Stopwatch(bool),
     Apple,
+    /// A pile of gold coins, added to the player's purse when picked
+    /// up.
+    Gold(i32),
+    Consumable(Consumable),
 }
 
 impl Item {
-    pub fn name(&self) -> &str {
-        match self {
-            Item::Sword => "Sword",
-            Item::Scythe => "Scythe",
-            Item::Hammer => "Hammer",
-            Item::Dagger => "Dagger",
-            Item::Shield => "Shield",
-            Item::VampireTeeth => "Garlic",
-            Item::Stopwatch(tick) => {
-                if *tick {
-                    "Stopwatch (tick)"
-                } else {
-                    "Stopwatch (tock)"
-                }
-            }
-            Item::Apple => "Apple",
-        }
+    pub fn name(&self) -> &'static str {
+        self.effect().name()
     }
 
-    pub fn description(&self) -> &str {
-        match self {
-            Item::Sword => "The trusty ol' Sword. Doubles your damage.",
-            Item::Scythe => "Ooh, sharp! Kills enemies under half health instantly.",
-            Item::Hammer => "What it lacks in edge, it makes up for in impact. Stuns enemies for a turn, making them unable to attack.",
-            Item::Dagger => "For when you want to watch your foes collapse from afar. Poisons the enemies for periodic damage, but halves your normal damage.",
-            Item::Shield => "Every knight's standard equipment. Blocks half of incoming damage.",
-            Item::VampireTeeth => "You feel a great apprehension towards this garlic. Keeping it in your pocket will remind you of your vampirous nature. Heal the damage you do to enemies.",
-            Item::Stopwatch(_) => "Tick, tock, tick, tock... Enemies move only every other turn!",
-            Item::Apple => "A tasty, red apple. Restores health to full when picked up.",
-        }
+    pub fn description(&self) -> &'static str {
+        self.effect().description()
     }
 
     pub fn sprite(&self) -> Sprite {
-        match self {
-            Item::Sword => Sprite(sprites::ITEM_SWORD),
-            Item::Scythe => Sprite(sprites::ITEM_SCYTHE),
-            Item::Hammer => Sprite(sprites::ITEM_HAMMER),
-            Item::Dagger => Sprite(sprites::ITEM_DAGGER),
-            Item::Shield => Sprite(sprites::ITEM_SHIELD),
-            Item::VampireTeeth => Sprite(sprites::ITEM_VAMPIRE_TEETH),
-            Item::Stopwatch(_) => Sprite(sprites::ITEM_STOPWATCH),
-            Item::Apple => Sprite(sprites::ITEM_APPLE),
-        }
+        self.effect().sprite()
+    }
+
+    /// The defense this item grants while carried.
+    pub fn defense(&self) -> Defense {
+        self.effect().defense()
+    }
+
+    /// The damage this item deals when thrown, before any item effects.
+    pub fn throw_damage(&self) -> i32 {
+        self.effect().throw_damage()
     }
 }
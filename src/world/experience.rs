@@ -0,0 +1,91 @@
+//! The player's experience, levels, and the perks picked on level up.
+use super::entity::Entity;
+
+const XP_PER_LEVEL: i32 = 10;
+const MAX_HEALTH_PERK: i32 = 4;
+const DAMAGE_PERK: i32 = 1;
+
+#[derive(Debug, Clone)]
+pub struct Experience {
+    pub level: i32,
+    /// XP gained since the last level up.
+    pub xp: i32,
+    /// Level ups the player hasn't picked a perk for yet.
+    pub unspent_perks: i32,
+}
+
+impl Experience {
+    pub const fn new() -> Experience {
+        Experience {
+            level: 1,
+            xp: 0,
+            unspent_perks: 0,
+        }
+    }
+
+    /// The XP needed to get from the current level to the next.
+    pub fn xp_to_next_level(&self) -> i32 {
+        self.level * XP_PER_LEVEL
+    }
+
+    /// Adds the XP, and returns how many levels were gained.
+    pub fn gain(&mut self, xp: i32) -> i32 {
+        self.xp += xp;
+        let mut levels_gained = 0;
+        while self.xp >= self.xp_to_next_level() {
+            self.xp -= self.xp_to_next_level();
+            self.level += 1;
+            self.unspent_perks += 1;
+            levels_gained += 1;
+        }
+        levels_gained
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Perk {
+    MaxHealth,
+    Damage,
+    ItemSlot,
+}
+
+impl Perk {
+    pub const ALL: [Perk; 3] = [Perk::MaxHealth, Perk::Damage, Perk::ItemSlot];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Perk::MaxHealth => "Vigor",
+            Perk::Damage => "Might",
+            Perk::ItemSlot => "Deep pockets",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Perk::MaxHealth => "Gain a heart of max health.",
+            Perk::Damage => "Deal 1 more base damage.",
+            Perk::ItemSlot => "Carry one more item.",
+        }
+    }
+
+    pub fn apply(&self, entity: &mut Entity) {
+        match self {
+            Perk::MaxHealth => {
+                if let Some(health) = &mut entity.health {
+                    health.max += MAX_HEALTH_PERK;
+                }
+                entity.heal(MAX_HEALTH_PERK);
+            }
+            Perk::Damage => {
+                if let Some(damage) = &mut entity.damage {
+                    damage.0 += DAMAGE_PERK;
+                }
+            }
+            Perk::ItemSlot => {
+                if let Some(inventory) = &mut entity.inventory {
+                    inventory.slots += 1;
+                }
+            }
+        }
+    }
+}
@@ -1,11 +1,13 @@
 use super::entities::*;
-use super::entity::{Entity, Inventory};
+use super::entity::{Consumable, Entity, Inventory, Item, TrapKind};
+use super::loot::{self, LootTable};
 use super::Room;
 use rand_core::{RngCore, SeedableRng};
 use rand_pcg::Pcg32;
 
 const ROOM_WIDTH: i32 = 11;
 const ROOM_HEIGHT: i32 = 7;
+const SHOP_WARES: usize = 3;
 
 fn door_left_of((x, y): (i32, i32)) -> Door {
     Door::new(x, y + ROOM_HEIGHT / 2, x - ROOM_WIDTH, y, (1, 0))
@@ -18,11 +20,13 @@ enum RoomType {
     MonsterRoom { count: i32 },
     StairsRoom,
     BossRoom,
+    SecretRoom,
+    ShopRoom,
 }
 
-fn get_rooms(level: i32) -> [RoomType; 8] {
+fn get_rooms(level: i32) -> Vec<RoomType> {
     use RoomType::*;
-    [
+    let mut rooms = vec![
         StartRoom,
         ItemRoom,
         MonsterRoom { count: 1 },
@@ -33,7 +37,14 @@ fn get_rooms(level: i32) -> [RoomType; 8] {
         MonsterRoom {
             count: level * 3 / 2,
         },
-    ]
+    ];
+    rooms.truncate(4 + level.min(4).max(0) as usize);
+    // Every other level has a shop, generated after the other rooms
+    // so that they're laid out the same as on levels without one
+    if level % 2 == 0 {
+        rooms.push(ShopRoom);
+    }
+    rooms
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -80,17 +91,43 @@ fn rand_enemy(rng: &mut Pcg32, level: i32) -> Entity {
     }
 }
 
-fn rand_item(rng: &mut Pcg32, player_inventory: &Inventory, level: i32) -> Entity {
-    let items_len = if level > 2 { PROTO_ITEMS.len() } else { 4 };
-    let mut index = (rng.next_u32() as usize) % items_len;
-    for _ in 0..3 {
-        if player_inventory.has_item(PROTO_ITEMS[index].drop.unwrap()) {
-            index = (index + 1) % items_len;
-        } else {
-            break;
-        }
+fn rand_trap(rng: &mut Pcg32, level: i32) -> Entity {
+    let r = rng.next_u32() % 100;
+    let mut trap = if level > 1 && r < 20 {
+        PROTO_FIRE_VENT.clone()
+    } else if r < 50 {
+        PROTO_PRESSURE_PLATE.clone()
+    } else if r < 65 {
+        PROTO_COBWEB.clone()
+    } else {
+        PROTO_SPIKES.clone()
+    };
+    if let Some(trap) = &mut trap.trap {
+        // Fire vents and cobwebs give themselves away anyway, no
+        // use hiding them
+        let obvious = trap.kind == TrapKind::FireVent || trap.kind == TrapKind::Cobweb;
+        trap.hidden = !obvious && rng.next_u32() % 100 < 40;
     }
-    PROTO_ITEMS[index].clone()
+    trap
+}
+
+fn rand_item(
+    rng: &mut Pcg32,
+    table: &LootTable,
+    player_inventory: &Inventory,
+    level: i32,
+    identification: bool,
+) -> Option<Entity> {
+    table
+        .roll(rng, level, player_inventory)
+        .filter(|&item| is_useful(item, identification))
+        .map(pickup_entity)
+}
+
+/// Identify scrolls are no use without the identification option, so
+/// they aren't handed out then.
+fn is_useful(item: Item, identification: bool) -> bool {
+    identification || item != Item::Consumable(Consumable::IdentifyScroll)
 }
 
 fn entity_at(x: i32, y: i32, entities: &[Entity]) -> bool {
@@ -103,12 +140,14 @@ fn entity_at(x: i32, y: i32, entities: &[Entity]) -> bool {
 #[derive(Debug, Clone)]
 pub struct WorldGenerator {
     rng: Pcg32,
+    identification: bool,
 }
 
 impl WorldGenerator {
-    pub fn new(seed: u64) -> WorldGenerator {
+    pub fn new(seed: u64, identification: bool) -> WorldGenerator {
         WorldGenerator {
             rng: Pcg32::seed_from_u64(seed),
+            identification,
         }
     }
 
@@ -122,7 +161,7 @@ impl WorldGenerator {
         let mut entities: Vec<Entity> = Vec::with_capacity(128);
         let mut rooms = Vec::with_capacity(room_types.len());
         let mut doors: Vec<Door> = Vec::with_capacity(rooms.len() * 4);
-        for room_type in &room_types[0..4 + level.min(4).max(0) as usize] {
+        for room_type in &room_types {
             let door_index = self.rng.next_u32() as usize % doors.len().max(1);
             let door = if doors.len() > door_index {
                 let door = doors
@@ -151,6 +190,35 @@ impl WorldGenerator {
             doors.extend(new_doors.into_iter());
         }
 
+        // Hide a bonus room behind one of the doors that would be sealed
+        if !doors.is_empty() && self.rng.next_u32() % 100 < 50 {
+            let first_door_index = self.rng.next_u32() as usize % doors.len();
+            for i in 0..doors.len() {
+                let door = doors[(first_door_index + i) % doors.len()];
+                let room_x = door.room_x + door.direction.0 * (ROOM_WIDTH - 1);
+                let room_y = door.room_y + door.direction.1 * (ROOM_HEIGHT - 1);
+                let (center_x, center_y) = (room_x + ROOM_WIDTH / 2, room_y + ROOM_HEIGHT / 2);
+                if rooms.iter().any(|room| room.contains(center_x, center_y)) {
+                    continue;
+                }
+
+                doors.remove((first_door_index + i) % doors.len());
+                entities.push(PROTO_SECRET_WALL.clone_at(door.x, door.y));
+                let (room, new_doors) = self.generate_room(
+                    &mut entities,
+                    inventory,
+                    &rooms,
+                    room_x,
+                    room_y,
+                    level,
+                    RoomType::SecretRoom,
+                );
+                rooms.push(room);
+                doors.extend(new_doors);
+                break;
+            }
+        }
+
         // Seal up the remaining doors
         for door in doors {
             entities.push(PROTO_WALL.clone_at(door.x, door.y));
@@ -242,12 +310,62 @@ impl WorldGenerator {
                         }
                     }
                 }
+
+                let consumable = rand_item(
+                    &mut self.rng,
+                    &loot::ROOM_CONSUMABLE,
+                    player_inventory,
+                    level,
+                    self.identification,
+                );
+                if let Some(mut consumable) = consumable {
+                    for _ in 0..50 {
+                        consumable.position.x = rand_range(&mut self.rng, min_x, max_x);
+                        consumable.position.y = rand_range(&mut self.rng, min_y, max_y);
+                        if !entity_at(consumable.position.x, consumable.position.y, entities) {
+                            entities.push(consumable);
+                            break;
+                        }
+                    }
+                }
+
+                if self.rng.next_u32() % 100 < 40 {
+                    let amount = rand_range(&mut self.rng, 2, 5 + level * 2);
+                    let mut gold = pickup_entity(Item::Gold(amount));
+                    for _ in 0..50 {
+                        gold.position.x = rand_range(&mut self.rng, min_x, max_x);
+                        gold.position.y = rand_range(&mut self.rng, min_y, max_y);
+                        if !entity_at(gold.position.x, gold.position.y, entities) {
+                            entities.push(gold);
+                            break;
+                        }
+                    }
+                }
+
+                let trap_count = rand_range(&mut self.rng, 0, level.min(3) + 1);
+                for _ in 0..trap_count {
+                    let mut trap = rand_trap(&mut self.rng, level);
+                    for _ in 0..50 {
+                        trap.position.x = rand_range(&mut self.rng, min_x, max_x);
+                        trap.position.y = rand_range(&mut self.rng, min_y, max_y);
+                        if !entity_at(trap.position.x, trap.position.y, entities) {
+                            entities.push(trap);
+                            break;
+                        }
+                    }
+                }
             }
             RoomType::ItemRoom => {
-                let mut item = rand_item(&mut self.rng, player_inventory, level);
-                item.position.x = center_x;
-                item.position.y = center_y;
-                entities.push(item);
+                let item = rand_item(
+                    &mut self.rng,
+                    &loot::ITEM_ROOM,
+                    player_inventory,
+                    level,
+                    self.identification,
+                );
+                if let Some(item) = item {
+                    entities.push(item.clone_at(center_x, center_y));
+                }
             }
             RoomType::StartRoom => {}
             RoomType::StairsRoom => {
@@ -256,6 +374,50 @@ impl WorldGenerator {
             RoomType::BossRoom => {
                 entities.push(PROTO_DRAGON.clone_at(center_x, center_y));
             }
+            RoomType::ShopRoom => {
+                // Out of the way of the doors, so that nobody bumps
+                // into the shopkeeper by accident
+                let (shopkeeper_x, shopkeeper_y) = (max_x, min_y);
+                entities.push(PROTO_SHOPKEEPER.clone_at(shopkeeper_x, shopkeeper_y));
+
+                let mut wares: Vec<Item> = Vec::with_capacity(SHOP_WARES);
+                for _ in 0..SHOP_WARES * 4 {
+                    if wares.len() == SHOP_WARES {
+                        break;
+                    }
+                    let entry = loot::SHOP.roll_entry(&mut self.rng, level, player_inventory);
+                    if let Some(entry) = entry {
+                        if !wares.contains(&entry.item)
+                            && is_useful(entry.item, self.identification)
+                        {
+                            let x = center_x - 2 + wares.len() as i32 * 2;
+                            let ware = ware_entity(entry.item, entry.rarity.price());
+                            entities.push(ware.clone_at(x, center_y));
+                            wares.push(entry.item);
+                        }
+                    }
+                }
+            }
+            RoomType::SecretRoom => {
+                let item = rand_item(
+                    &mut self.rng,
+                    &loot::SECRET_ROOM,
+                    player_inventory,
+                    level,
+                    self.identification,
+                );
+                match item {
+                    Some(item) if item.drop != Some(Item::Apple) => {
+                        entities.push(item.clone_at(center_x, center_y));
+                    }
+                    _ => {
+                        // A whole bunch of apples, then
+                        for x in center_x - 1..=center_x + 1 {
+                            entities.push(PROTO_APPLE.clone_at(x, center_y));
+                        }
+                    }
+                }
+            }
         }
 
         if 30 < self.rng.next_u32() % 100 {
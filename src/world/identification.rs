@@ -0,0 +1,146 @@
+//! Unidentified items. Potions and scrolls get shuffled appearances
+//! for each run, and equipment is only a "??? blade" or such until
+//! it's carried. All of it is only in play if the identification
+//! option is enabled.
+//!
+//! The appearances are shuffled with the run's seed, so a seed always
+//! gets the same ones. There's no saving of games, though, so what
+//! the player has identified only lasts until the run is over.
+use super::entity::{Consumable, Item, Sprite};
+use crate::sprites::{self, SpriteData};
+use rand_core::RngCore;
+use rand_pcg::Pcg32;
+
+const POTIONS: [Consumable; 4] = [
+    Consumable::HealingPotion,
+    Consumable::HastePotion,
+    Consumable::StrengthPotion,
+    Consumable::RegenerationPotion,
+];
+const POTION_APPEARANCES: [(&str, SpriteData); 4] = [
+    ("Red potion", sprites::POTION_HEALING),
+    ("Yellow potion", sprites::POTION_HASTE),
+    ("Orange potion", sprites::POTION_STRENGTH),
+    ("Green potion", sprites::POTION_REGENERATION),
+];
+
+const SCROLLS: [Consumable; 4] = [
+    Consumable::TeleportScroll,
+    Consumable::MappingScroll,
+    Consumable::FearScroll,
+    Consumable::IdentifyScroll,
+];
+const SCROLL_APPEARANCES: [(&str, SpriteData); 4] = [
+    ("Blue-sealed scroll", sprites::SCROLL_TELEPORT),
+    ("Green-sealed scroll", sprites::SCROLL_MAPPING),
+    ("Purple-sealed scroll", sprites::SCROLL_FEAR),
+    ("Red-sealed scroll", sprites::SCROLL_IDENTIFY),
+];
+
+/// What the player knows about the items in this run.
+#[derive(Debug, Clone)]
+pub struct Identification {
+    enabled: bool,
+    /// Indices into POTION_APPEARANCES, in the order of POTIONS.
+    potion_appearances: [usize; 4],
+    /// Indices into SCROLL_APPEARANCES, in the order of SCROLLS.
+    scroll_appearances: [usize; 4],
+    identified: Vec<Item>,
+}
+
+impl Identification {
+    pub fn new(rng: &mut Pcg32, enabled: bool) -> Identification {
+        Identification {
+            enabled,
+            potion_appearances: shuffled_indices(rng),
+            scroll_appearances: shuffled_indices(rng),
+            identified: Vec::new(),
+        }
+    }
+
+    pub fn is_identified(&self, item: Item) -> bool {
+        match item {
+            Item::Apple | Item::Gold(_) => true,
+            Item::Consumable(Consumable::ThrowingKnife) => true,
+            _ => !self.enabled || self.identified.contains(&kind(item)),
+        }
+    }
+
+    /// Identifies the item. Returns true if it wasn't identified
+    /// before.
+    pub fn identify(&mut self, item: Item) -> bool {
+        if self.is_identified(item) {
+            false
+        } else {
+            self.identified.push(kind(item));
+            true
+        }
+    }
+
+    /// The item's name, as far as the player knows.
+    pub fn name(&self, item: Item) -> &'static str {
+        if self.is_identified(item) {
+            return item.name();
+        }
+        match item {
+            Item::Consumable(consumable) => self.appearance(consumable).0,
+            Item::Sword | Item::Scythe | Item::Dagger => "??? blade",
+            Item::Hammer => "??? bludgeon",
+            Item::Shield | Item::LeatherArmor | Item::ChainMail => "??? armor",
+            _ => "??? trinket",
+        }
+    }
+
+    /// The item's description, as far as the player knows.
+    pub fn description(&self, item: Item) -> &'static str {
+        if self.is_identified(item) {
+            return item.description();
+        }
+        match item {
+            Item::Consumable(consumable) if POTIONS.contains(&consumable) => {
+                "A potion you haven't seen before. Drink it to find out what it does."
+            }
+            Item::Consumable(_) => {
+                "The writing makes no sense to you. Read it to find out what it does."
+            }
+            _ => "Who knows what it does? Carry it with you to find out.",
+        }
+    }
+
+    /// The item's sprite. Potions and scrolls look the same whether
+    /// they're identified or not.
+    pub fn sprite(&self, item: Item) -> Sprite {
+        match item {
+            Item::Consumable(consumable) if self.enabled => Sprite(self.appearance(consumable).1),
+            _ => item.sprite(),
+        }
+    }
+
+    fn appearance(&self, consumable: Consumable) -> (&'static str, SpriteData) {
+        if let Some(i) = POTIONS.iter().position(|&c| c == consumable) {
+            POTION_APPEARANCES[self.potion_appearances[i]]
+        } else if let Some(i) = SCROLLS.iter().position(|&c| c == consumable) {
+            SCROLL_APPEARANCES[self.scroll_appearances[i]]
+        } else {
+            (consumable.name(), consumable.sprite().0)
+        }
+    }
+}
+
+/// The Stopwatch changes between tick and tock every turn, but it's
+/// still the same item.
+fn kind(item: Item) -> Item {
+    match item {
+        Item::Stopwatch(_) => Item::Stopwatch(false),
+        item => item,
+    }
+}
+
+fn shuffled_indices(rng: &mut Pcg32) -> [usize; 4] {
+    let mut indices = [0, 1, 2, 3];
+    for i in (1..indices.len()).rev() {
+        let j = rng.next_u32() as usize % (i + 1);
+        indices.swap(i, j);
+    }
+    indices
+}
@@ -0,0 +1,371 @@
+//! What the items do. Each item is a type implementing ItemEffect,
+//! and Item::effect maps the items to them, so a new item only needs
+//! a variant in Item and an implementation here.
+use super::entity::{Consumable, Defense, Entity, Item, Sprite, StatusEffect, StatusKind};
+use crate::sprites;
+
+const DAGGER_POISON_DURATION: i32 = 6;
+
+/// Things that happen to the whole turn because of the player's
+/// items, collected by ItemEffect::on_turn.
+#[derive(Debug, Clone, Default)]
+pub struct TurnEffects {
+    /// The monsters don't get to act this turn.
+    pub time_stopped: bool,
+}
+
+/// The behavior of an item. The hooks are called for every item in
+/// the carrier's inventory, in inventory order, except for
+/// modify_damage, which goes by priority.
+pub trait ItemEffect {
+    fn name(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    fn sprite(&self) -> Sprite;
+
+    /// The defense this item grants while carried.
+    fn defense(&self) -> Defense {
+        Defense::new(0, 0)
+    }
+
+    /// The damage this item deals when thrown, before any item
+    /// effects.
+    fn throw_damage(&self) -> i32 {
+        1
+    }
+
+    /// How many tiles the carrier's attacks push the target back.
+    fn knockback(&self) -> i32 {
+        0
+    }
+
+    /// Changes the carrier's damage, regardless of who they're
+    /// attacking. Shown in the stats panel.
+    fn modify_damage(&self, damage: i32) -> i32 {
+        damage
+    }
+
+    /// When this item's modify_damage is applied, relative to the
+    /// carrier's other items. Lower goes first, and items with the
+    /// same priority go in inventory order.
+    fn priority(&self) -> i32 {
+        0
+    }
+
+    /// Called when the carrier attacks `defender`, after every item's
+    /// modify_damage and the defender's defense have been applied.
+    /// Returns the damage of this particular attack.
+    fn on_attack(&self, damage: i32, _defender: &Entity) -> i32 {
+        damage
+    }
+
+    /// Called for every target the carrier's attack hits, after the
+    /// damage has been dealt.
+    fn on_hit(&self, _target: &mut Entity) {}
+
+    /// Called once the carrier's attack is over, with the damage it
+    /// dealt in total.
+    fn on_damage_dealt(&self, _carrier: &mut Entity, _damage_dealt: i32) {}
+
+    /// Called when the carrier is hit, after the damage has been
+    /// taken.
+    fn on_damaged(&self, _carrier: &mut Entity, _damage_taken: i32) {}
+
+    /// Called at the start of each of the player's turns. Returns
+    /// the item this item turns into, if it changes.
+    fn on_turn(&self, _effects: &mut TurnEffects) -> Option<Item> {
+        None
+    }
+}
+
+impl Item {
+    pub fn effect(&self) -> &dyn ItemEffect {
+        match self {
+            Item::Sword => &Sword,
+            Item::Scythe => &Scythe,
+            Item::Hammer => &Hammer,
+            Item::Dagger => &Dagger,
+            Item::Shield => &Shield,
+            Item::LeatherArmor => &LeatherArmor,
+            Item::ChainMail => &ChainMail,
+            Item::VampireTeeth => &VampireTeeth,
+            Item::Stopwatch(true) => &Stopwatch { tick: true },
+            Item::Stopwatch(false) => &Stopwatch { tick: false },
+            Item::Apple => &Apple,
+            Item::Gold(_) => &Gold,
+            Item::Consumable(consumable) => consumable,
+        }
+    }
+}
+
+struct Sword;
+
+impl ItemEffect for Sword {
+    fn name(&self) -> &'static str {
+        "Sword"
+    }
+
+    fn description(&self) -> &'static str {
+        "The trusty ol' Sword. Doubles your damage."
+    }
+
+    fn sprite(&self) -> Sprite {
+        Sprite(sprites::ITEM_SWORD)
+    }
+
+    fn throw_damage(&self) -> i32 {
+        2
+    }
+
+    fn modify_damage(&self, damage: i32) -> i32 {
+        damage * 2
+    }
+}
+
+struct Scythe;
+
+impl ItemEffect for Scythe {
+    fn name(&self) -> &'static str {
+        "Scythe"
+    }
+
+    fn description(&self) -> &'static str {
+        "Ooh, sharp! Kills enemies under half health instantly."
+    }
+
+    fn sprite(&self) -> Sprite {
+        Sprite(sprites::ITEM_SCYTHE)
+    }
+
+    fn throw_damage(&self) -> i32 {
+        2
+    }
+
+    fn on_attack(&self, damage: i32, defender: &Entity) -> i32 {
+        match &defender.health {
+            Some(health) if health.current <= health.max / 2 => health.current,
+            _ => damage,
+        }
+    }
+}
+
+struct Hammer;
+
+impl ItemEffect for Hammer {
+    fn name(&self) -> &'static str {
+        "Hammer"
+    }
+
+    fn description(&self) -> &'static str {
+        "What it lacks in edge, it makes up for in impact. Stuns enemies for a turn, making them unable to attack, and knocks them back a tile."
+    }
+
+    fn sprite(&self) -> Sprite {
+        Sprite(sprites::ITEM_HAMMER)
+    }
+
+    fn throw_damage(&self) -> i32 {
+        3
+    }
+
+    fn knockback(&self) -> i32 {
+        1
+    }
+
+    fn on_hit(&self, target: &mut Entity) {
+        target.apply_status_effect(StatusEffect::new(StatusKind::Stun, 1));
+    }
+}
+
+struct Dagger;
+
+impl ItemEffect for Dagger {
+    fn name(&self) -> &'static str {
+        "Dagger"
+    }
+
+    fn description(&self) -> &'static str {
+        "For when you want to watch your foes collapse from afar. Poisons the enemies for periodic damage, but halves your normal damage."
+    }
+
+    fn sprite(&self) -> Sprite {
+        Sprite(sprites::ITEM_DAGGER)
+    }
+
+    fn throw_damage(&self) -> i32 {
+        2
+    }
+
+    fn modify_damage(&self, damage: i32) -> i32 {
+        damage / 2
+    }
+
+    // After the Sword's doubling, so that carrying both evens out
+    fn priority(&self) -> i32 {
+        1
+    }
+
+    fn on_hit(&self, target: &mut Entity) {
+        let poison = StatusEffect::new(StatusKind::Poison, DAGGER_POISON_DURATION);
+        target.apply_status_effect(poison);
+    }
+}
+
+struct Shield;
+
+impl ItemEffect for Shield {
+    fn name(&self) -> &'static str {
+        "Shield"
+    }
+
+    fn description(&self) -> &'static str {
+        "Every knight's standard equipment. Blocks half of incoming damage, and can be used to bash enemies [V], knocking them back two tiles."
+    }
+
+    fn sprite(&self) -> Sprite {
+        Sprite(sprites::ITEM_SHIELD)
+    }
+
+    fn defense(&self) -> Defense {
+        Defense::new(0, 50)
+    }
+}
+
+struct LeatherArmor;
+
+impl ItemEffect for LeatherArmor {
+    fn name(&self) -> &'static str {
+        "Leather armor"
+    }
+
+    fn description(&self) -> &'static str {
+        "Hardened leather, worn and scuffed. Blocks 1 damage from every hit."
+    }
+
+    fn sprite(&self) -> Sprite {
+        Sprite(sprites::ITEM_LEATHER_ARMOR)
+    }
+
+    fn defense(&self) -> Defense {
+        Defense::new(1, 0)
+    }
+}
+
+struct ChainMail;
+
+impl ItemEffect for ChainMail {
+    fn name(&self) -> &'static str {
+        "Chain mail"
+    }
+
+    fn description(&self) -> &'static str {
+        "Heavy rings of steel. Blocks 1 damage from every hit, and a quarter of the rest."
+    }
+
+    fn sprite(&self) -> Sprite {
+        Sprite(sprites::ITEM_CHAIN_MAIL)
+    }
+
+    fn defense(&self) -> Defense {
+        Defense::new(1, 25)
+    }
+}
+
+struct VampireTeeth;
+
+impl ItemEffect for VampireTeeth {
+    fn name(&self) -> &'static str {
+        "Garlic"
+    }
+
+    fn description(&self) -> &'static str {
+        "You feel a great apprehension towards this garlic. Keeping it in your pocket will remind you of your vampirous nature. Heal the damage you do to enemies."
+    }
+
+    fn sprite(&self) -> Sprite {
+        Sprite(sprites::ITEM_VAMPIRE_TEETH)
+    }
+
+    fn on_damage_dealt(&self, carrier: &mut Entity, damage_dealt: i32) {
+        carrier.heal(damage_dealt / 2);
+    }
+}
+
+struct Stopwatch {
+    tick: bool,
+}
+
+impl ItemEffect for Stopwatch {
+    fn name(&self) -> &'static str {
+        if self.tick {
+            "Stopwatch (tick)"
+        } else {
+            "Stopwatch (tock)"
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        "Tick, tock, tick, tock... Enemies move only every other turn!"
+    }
+
+    fn sprite(&self) -> Sprite {
+        Sprite(sprites::ITEM_STOPWATCH)
+    }
+
+    fn on_turn(&self, effects: &mut TurnEffects) -> Option<Item> {
+        effects.time_stopped |= self.tick;
+        Some(Item::Stopwatch(!self.tick))
+    }
+}
+
+struct Apple;
+
+impl ItemEffect for Apple {
+    fn name(&self) -> &'static str {
+        "Apple"
+    }
+
+    fn description(&self) -> &'static str {
+        "A tasty, red apple. Restores health to full when picked up."
+    }
+
+    fn sprite(&self) -> Sprite {
+        Sprite(sprites::ITEM_APPLE)
+    }
+}
+
+struct Gold;
+
+impl ItemEffect for Gold {
+    fn name(&self) -> &'static str {
+        "Gold"
+    }
+
+    fn description(&self) -> &'static str {
+        "Shiny coins. The shopkeepers down here will happily take them off your hands."
+    }
+
+    fn sprite(&self) -> Sprite {
+        Sprite(sprites::ITEM_GOLD)
+    }
+}
+
+impl ItemEffect for Consumable {
+    fn name(&self) -> &'static str {
+        Consumable::name(self)
+    }
+
+    fn description(&self) -> &'static str {
+        Consumable::description(self)
+    }
+
+    fn sprite(&self) -> Sprite {
+        Consumable::sprite(self)
+    }
+
+    fn throw_damage(&self) -> i32 {
+        match self {
+            Consumable::ThrowingKnife => 3,
+            _ => 1,
+        }
+    }
+}
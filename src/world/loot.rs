@@ -0,0 +1,348 @@
+//! Loot tables, which decide what the item rooms, secret rooms, shops
+//! and monsters hand out.
+use super::entity::{Consumable, Inventory, Item};
+use rand_core::{RngCore, SeedableRng};
+use rand_pcg::Pcg32;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+}
+
+impl Rarity {
+    pub fn weight(&self) -> u32 {
+        match self {
+            Rarity::Common => 6,
+            Rarity::Uncommon => 3,
+            Rarity::Rare => 1,
+        }
+    }
+
+    /// What the item costs in a shop.
+    pub fn price(&self) -> i32 {
+        match self {
+            Rarity::Common => 10,
+            Rarity::Uncommon => 20,
+            Rarity::Rare => 35,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LootEntry {
+    pub item: Item,
+    pub rarity: Rarity,
+    /// The first and last level this entry can drop on.
+    pub levels: (i32, i32),
+    /// Unique entries won't drop if the player is already carrying
+    /// the item.
+    pub unique: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct LootTable {
+    pub name: &'static str,
+    pub entries: &'static [LootEntry],
+    /// The weight of rolling nothing at all, compared to the entries'
+    /// rarity weights.
+    pub nothing_weight: u32,
+}
+
+impl LootTable {
+    pub fn roll(&self, rng: &mut Pcg32, level: i32, inventory: &Inventory) -> Option<Item> {
+        self.roll_entry(rng, level, inventory)
+            .map(|entry| entry.item)
+    }
+
+    /// Like LootTable::roll, but returns the whole entry, for when
+    /// the rarity matters too.
+    pub fn roll_entry(
+        &self,
+        rng: &mut Pcg32,
+        level: i32,
+        inventory: &Inventory,
+    ) -> Option<&'static LootEntry> {
+        let total_weight = self.nothing_weight
+            + self
+                .available_entries(level, inventory)
+                .map(|entry| entry.rarity.weight())
+                .sum::<u32>();
+        if total_weight == 0 {
+            return None;
+        }
+
+        let mut roll = rng.next_u32() % total_weight;
+        for entry in self.available_entries(level, inventory) {
+            let weight = entry.rarity.weight();
+            if roll < weight {
+                return Some(entry);
+            }
+            roll -= weight;
+        }
+        None
+    }
+
+    fn available_entries<'a>(
+        &'a self,
+        level: i32,
+        inventory: &'a Inventory,
+    ) -> impl Iterator<Item = &'static LootEntry> + 'a {
+        self.entries.iter().filter(move |entry| {
+            let (first_level, last_level) = entry.levels;
+            first_level <= level
+                && level <= last_level
+                && !(entry.unique && inventory.has_item(entry.item))
+        })
+    }
+}
+
+const fn equipment(item: Item, rarity: Rarity, first_level: i32) -> LootEntry {
+    LootEntry {
+        item,
+        rarity,
+        levels: (first_level, i32::MAX),
+        unique: true,
+    }
+}
+
+const fn consumable(item: Item, rarity: Rarity, first_level: i32) -> LootEntry {
+    LootEntry {
+        item,
+        rarity,
+        levels: (first_level, i32::MAX),
+        unique: false,
+    }
+}
+
+pub const ITEM_ROOM: LootTable = LootTable {
+    name: "Item room",
+    entries: &[
+        equipment(Item::Sword, Rarity::Common, 1),
+        equipment(Item::Shield, Rarity::Common, 1),
+        equipment(Item::Hammer, Rarity::Common, 1),
+        equipment(Item::Dagger, Rarity::Common, 1),
+        equipment(Item::LeatherArmor, Rarity::Common, 1),
+        equipment(Item::ChainMail, Rarity::Uncommon, 2),
+        equipment(Item::VampireTeeth, Rarity::Uncommon, 3),
+        equipment(Item::Scythe, Rarity::Uncommon, 3),
+        equipment(Item::Stopwatch(false), Rarity::Rare, 3),
+    ],
+    nothing_weight: 0,
+};
+
+pub const SECRET_ROOM: LootTable = LootTable {
+    name: "Secret room",
+    entries: &[
+        consumable(Item::Apple, Rarity::Common, 1),
+        consumable(
+            Item::Consumable(Consumable::StrengthPotion),
+            Rarity::Uncommon,
+            1,
+        ),
+        consumable(
+            Item::Consumable(Consumable::MappingScroll),
+            Rarity::Uncommon,
+            1,
+        ),
+        equipment(Item::Hammer, Rarity::Uncommon, 1),
+        equipment(Item::Dagger, Rarity::Uncommon, 1),
+        equipment(Item::ChainMail, Rarity::Uncommon, 1),
+        equipment(Item::VampireTeeth, Rarity::Uncommon, 1),
+        equipment(Item::Scythe, Rarity::Uncommon, 2),
+        equipment(Item::Stopwatch(false), Rarity::Rare, 2),
+    ],
+    nothing_weight: 0,
+};
+
+pub const ROOM_CONSUMABLE: LootTable = LootTable {
+    name: "Room consumable",
+    entries: &[
+        consumable(
+            Item::Consumable(Consumable::HealingPotion),
+            Rarity::Common,
+            1,
+        ),
+        consumable(
+            Item::Consumable(Consumable::ThrowingKnife),
+            Rarity::Common,
+            1,
+        ),
+        consumable(
+            Item::Consumable(Consumable::HastePotion),
+            Rarity::Uncommon,
+            1,
+        ),
+        consumable(
+            Item::Consumable(Consumable::StrengthPotion),
+            Rarity::Uncommon,
+            1,
+        ),
+        consumable(
+            Item::Consumable(Consumable::TeleportScroll),
+            Rarity::Uncommon,
+            2,
+        ),
+        consumable(
+            Item::Consumable(Consumable::RegenerationPotion),
+            Rarity::Uncommon,
+            2,
+        ),
+        consumable(
+            Item::Consumable(Consumable::IdentifyScroll),
+            Rarity::Uncommon,
+            1,
+        ),
+        consumable(Item::Consumable(Consumable::MappingScroll), Rarity::Rare, 1),
+        consumable(Item::Consumable(Consumable::FearScroll), Rarity::Rare, 2),
+    ],
+    nothing_weight: 20,
+};
+
+pub const SKELETON_DROP: LootTable = LootTable {
+    name: "Skeleton drop",
+    entries: &[
+        consumable(Item::Apple, Rarity::Uncommon, 1),
+        consumable(
+            Item::Consumable(Consumable::ThrowingKnife),
+            Rarity::Uncommon,
+            1,
+        ),
+        consumable(Item::Consumable(Consumable::HealingPotion), Rarity::Rare, 1),
+        equipment(Item::Sword, Rarity::Rare, 2),
+        equipment(Item::Shield, Rarity::Rare, 2),
+        equipment(Item::Dagger, Rarity::Rare, 2),
+    ],
+    nothing_weight: 30,
+};
+
+pub const ZOMBIE_DROP: LootTable = LootTable {
+    name: "Zombie drop",
+    entries: &[
+        consumable(Item::Apple, Rarity::Common, 1),
+        consumable(Item::Consumable(Consumable::HealingPotion), Rarity::Rare, 1),
+        equipment(Item::LeatherArmor, Rarity::Rare, 1),
+        equipment(Item::Hammer, Rarity::Rare, 2),
+    ],
+    nothing_weight: 30,
+};
+
+pub const SHOPKEEPER_DROP: LootTable = LootTable {
+    name: "Shopkeeper drop",
+    entries: &[
+        consumable(
+            Item::Consumable(Consumable::HealingPotion),
+            Rarity::Common,
+            1,
+        ),
+        consumable(
+            Item::Consumable(Consumable::StrengthPotion),
+            Rarity::Uncommon,
+            1,
+        ),
+        consumable(
+            Item::Consumable(Consumable::RegenerationPotion),
+            Rarity::Uncommon,
+            1,
+        ),
+    ],
+    nothing_weight: 0,
+};
+
+pub const SHOP: LootTable = LootTable {
+    name: "Shop",
+    entries: &[
+        equipment(Item::Sword, Rarity::Common, 1),
+        equipment(Item::Shield, Rarity::Common, 1),
+        equipment(Item::LeatherArmor, Rarity::Common, 1),
+        equipment(Item::Hammer, Rarity::Uncommon, 1),
+        equipment(Item::Dagger, Rarity::Uncommon, 1),
+        equipment(Item::ChainMail, Rarity::Uncommon, 2),
+        equipment(Item::VampireTeeth, Rarity::Rare, 3),
+        equipment(Item::Scythe, Rarity::Rare, 3),
+        consumable(
+            Item::Consumable(Consumable::HealingPotion),
+            Rarity::Common,
+            1,
+        ),
+        consumable(
+            Item::Consumable(Consumable::ThrowingKnife),
+            Rarity::Common,
+            1,
+        ),
+        consumable(
+            Item::Consumable(Consumable::StrengthPotion),
+            Rarity::Uncommon,
+            1,
+        ),
+        consumable(
+            Item::Consumable(Consumable::MappingScroll),
+            Rarity::Uncommon,
+            1,
+        ),
+        consumable(
+            Item::Consumable(Consumable::RegenerationPotion),
+            Rarity::Uncommon,
+            2,
+        ),
+        consumable(
+            Item::Consumable(Consumable::IdentifyScroll),
+            Rarity::Common,
+            1,
+        ),
+    ],
+    nothing_weight: 0,
+};
+
+pub const TABLES: [&LootTable; 7] = [
+    &ITEM_ROOM,
+    &SECRET_ROOM,
+    &ROOM_CONSUMABLE,
+    &SHOP,
+    &SKELETON_DROP,
+    &ZOMBIE_DROP,
+    &SHOPKEEPER_DROP,
+];
+
+/// Parses seed ranges written like `0..1000`.
+pub fn parse_seed_range(range: &str) -> Option<Range<u64>> {
+    let mut parts = range.split("..");
+    let start = parts.next()?.trim().parse().ok()?;
+    let end = parts.next()?.trim().parse().ok()?;
+    if parts.next().is_some() || start >= end {
+        None
+    } else {
+        Some(start..end)
+    }
+}
+
+/// Prints out how often each item drops from each table, on levels
+/// 1-5, when rolled once per seed with an empty inventory.
+pub fn print_distribution(seeds: Range<u64>) {
+    let inventory = Inventory::new(0, 0);
+    let seed_count = seeds.end - seeds.start;
+    for table in &TABLES {
+        for level in 1..=5 {
+            let mut counts: Vec<(Option<Item>, u64)> = Vec::new();
+            for seed in seeds.clone() {
+                let mut rng = Pcg32::seed_from_u64(seed);
+                let item = table.roll(&mut rng, level, &inventory);
+                if let Some((_, count)) = counts.iter_mut().find(|(i, _)| *i == item) {
+                    *count += 1;
+                } else {
+                    counts.push((item, 1));
+                }
+            }
+            counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+            println!("{}, level {} ({} seeds):", table.name, level, seed_count);
+            for (item, count) in counts {
+                let name = item.as_ref().map(|item| item.name()).unwrap_or("Nothing");
+                let percentage = count as f32 / seed_count as f32 * 100.0;
+                println!("  {:<20}{:>6.2}%", name, percentage);
+            }
+        }
+    }
+}
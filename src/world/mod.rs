@@ -1,17 +1,73 @@
 mod ai;
+mod context;
 pub mod entities;
 mod entity;
+mod experience;
 mod generator;
+mod identification;
+mod items;
+pub mod loot;
+mod status;
 
+use crate::audio::Sound;
 use crate::{layers, sprites};
 use ai::AiTrait;
+use context::{Knockback, TurnContext};
 use entities::*;
 use entity::*;
 use generator::WorldGenerator;
-
-use fae::{Font, GraphicsContext, Spritesheet};
-
-pub use entity::Item;
+use identification::Identification;
+use items::TurnEffects;
+use status::take_damage;
+
+use fae::{Alignment, Font, GraphicsContext, Spritesheet};
+use rand_core::RngCore;
+use rand_pcg::Pcg32;
+use std::ops::Range;
+
+pub use context::GameOptions;
+pub use entity::{Entity, Item};
+pub use experience::Perk;
+
+/// The stream used for the randomness in gameplay effects, so that
+/// they don't affect the level generator's sequence.
+const GAMEPLAY_RNG_STREAM: u64 = 1;
+/// The stream for the combat rolls, separate from the others for the
+/// same reason.
+const COMBAT_RNG_STREAM: u64 = 2;
+/// The stream for shuffling the appearances of unidentified items.
+const IDENTIFICATION_RNG_STREAM: u64 = 3;
+
+const HEALING_POTION_HEALTH: i32 = 8;
+const HASTE_DURATION: i32 = 10;
+const STRENGTH_DURATION: i32 = 10;
+const REGENERATION_DURATION: i32 = 12;
+const COBWEB_SLOW_DURATION: i32 = 4;
+const FEAR_DURATION: i32 = 8;
+const KNOCKBACK_COLLISION_DAMAGE: i32 = 2;
+const SHIELD_BASH_DAMAGE: i32 = 1;
+const SHIELD_BASH_KNOCKBACK: i32 = 2;
+pub const THROW_RANGE: i32 = 5;
+/// The default size of a tile on the screen, in pixels.
+pub const TILE_SIZE: f32 = 48.0;
+
+const MAP_WALL_COLOR: (f32, f32, f32, f32) = (0.4, 0.4, 0.45, 1.0);
+const MAP_FLOOR_COLOR: (f32, f32, f32, f32) = (0.12, 0.12, 0.15, 1.0);
+const MAP_DOOR_COLOR: (f32, f32, f32, f32) = (0.6, 0.42, 0.22, 1.0);
+const MAP_STAIRS_COLOR: (f32, f32, f32, f32) = (0.55, 0.25, 0.75, 1.0);
+const MAP_PLAYER_COLOR: (f32, f32, f32, f32) = (0.95, 0.9, 0.35, 1.0);
+
+/// How far attackers lunge towards their targets, in tiles.
+const LUNGE_DISTANCE: f32 = 0.3;
+/// How far the damage and healing numbers float up, in tiles.
+const FLOATING_NUMBER_RISE: f32 = 0.6;
+const DAMAGE_COLOR: (f32, f32, f32) = (1.0, 0.35, 0.3);
+const POISON_COLOR: (f32, f32, f32) = (0.45, 0.9, 0.3);
+const HEAL_COLOR: (f32, f32, f32) = (0.5, 0.8, 1.0);
+const NO_ANIMATED_STEPS: Range<usize> = 0..0;
+/// How fast the smoothed camera catches up with the player. Larger is
+/// faster, about 1/x seconds to get most of the way there.
+const CAMERA_FOLLOW_SPEED: f32 = 8.0;
 
 /// Represents an iterator over all entities except for one. Used when
 /// running updates for a that one entity, if it needs to interact
@@ -21,15 +77,54 @@ pub use entity::Item;
 pub type EntityIter<'a> =
     std::iter::Chain<std::slice::IterMut<'a, Entity>, std::slice::IterMut<'a, Entity>>;
 
+/// An entity appearing or disappearing during a round.
+#[derive(Debug, Clone)]
+enum EntityTransition {
+    /// The entity at the index was spawned.
+    Spawn(usize),
+    /// The entity was marked for death. It's a copy, since its slot
+    /// might be taken by a new entity before the animation is over.
+    Despawn(Box<Entity>),
+}
+
 #[derive(Debug, Clone)]
 pub enum PlayerAction {
     MoveUp,
     MoveDown,
     MoveRight,
     MoveLeft,
+    /// The diagonal moves only work with the diagonal movement
+    /// option enabled.
+    MoveUpLeft,
+    MoveUpRight,
+    MoveDownLeft,
+    MoveDownRight,
     Pickup,
+    /// Picks up the item on the floor, putting the item in the given
+    /// inventory slot on the floor in its place.
+    Swap(usize),
+    Drop(usize),
+    /// Buys the ware the player is standing on, and picks it up if
+    /// there's room for it.
+    Buy,
+    /// Bashes whatever is in the given direction with the shield,
+    /// knocking it back. Only works if the player has a shield.
+    ShieldBash(i32, i32),
+    /// Uses the consumable in the given belt slot.
+    Use(usize),
+    /// Throws an item towards the given tile.
+    Throw(ThrowSource, i32, i32),
     Wait,
     NextLevel,
+    /// Picks a perk for a level up. Doesn't take a turn.
+    ChoosePerk(Perk),
+}
+
+/// Where a thrown item is taken from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThrowSource {
+    Inventory(usize),
+    Belt(usize),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -48,10 +143,16 @@ impl Room {
 
 pub struct World {
     generator: WorldGenerator,
+    rng: Pcg32,
+    context: TurnContext,
+    identification: Identification,
     level: i32,
 
     rooms: Vec<Room>,
     discovered_rooms: Vec<Room>,
+    /// The positions of the doors and hidden doors of this level, so
+    /// the map can show the ones that have been opened.
+    doorways: Vec<Position>,
 
     /// An ever-permanent entity collection, which should be
     /// initialized all at once, and never removed from.
@@ -61,15 +162,50 @@ pub struct World {
     ais: Vec<Option<Box<dyn AiTrait>>>,
 
     /// Every round we'll copy the whole state over to this Vec, just
-    /// so animations can be done nicely. Sounds wasteful, probably
-    /// is, but honestly, the whole game state is probably a few megs
-    /// at most.
+    /// so we can tell what changed during the round. Sounds wasteful,
+    /// probably is, but honestly, the whole game state is probably a
+    /// few megs at most.
     previous_round_entities: Option<Vec<Entity>>,
+    /// The positions of the entities, and whether they're alive, at
+    /// the start of the round and after each step of it where
+    /// something changed. Played one step at a time with sequenced
+    /// animations.
+    animation_steps: Vec<Vec<(Position, bool)>>,
+    /// The step that's being animated, None if the round hasn't
+    /// started animating yet.
+    animation_step: Option<usize>,
+    /// How far along the current step is, 0.0 - 1.0.
+    animation_progress: f32,
+    /// The combat events of the round, with the animation step and
+    /// the index of the entity they happened in.
+    combat_events: Vec<(usize, usize, CombatEvent)>,
+    /// The animation steps whose combat events are being played.
+    animated_steps: Range<usize>,
+    /// The entities that appeared and disappeared this round, with the
+    /// animation step they did it in.
+    transitions: Vec<(usize, EntityTransition)>,
+    /// The sounds of the round, with the animation step they happened
+    /// in.
+    sounds: Vec<(usize, Sound)>,
+    /// The sounds of the steps that have started animating, waiting
+    /// to be played. See World::take_sounds.
+    sounds_to_play: Vec<Sound>,
+    /// The entities that have sprung the traps at a position this
+    /// round, so that an entity knocked onto a trap doesn't spring
+    /// it again on its own turn.
+    trap_triggers: Vec<(usize, Position)>,
     animation_timer: f32,
+    /// The size of the tiles, unless the player's room wouldn't fit
+    /// on the screen with it.
+    tile_size: f32,
+    /// The point the camera is centered on, in tiles, before it's
+    /// kept inside the level. None until the camera has been moved
+    /// for the first time on the level.
+    camera: Option<(f32, f32)>,
 }
 
 impl World {
-    pub fn new() -> World {
+    pub fn new(options: GameOptions) -> World {
         let seed = if cfg!(debug_assertions) {
             1234
         } else {
@@ -79,15 +215,32 @@ impl World {
                 .map(|st| st.as_secs())
                 .unwrap_or(1234)
         };
+        let mut identification_rng = Pcg32::new(seed, IDENTIFICATION_RNG_STREAM);
+        let identification = Identification::new(&mut identification_rng, options.identification);
         let mut world = World {
-            generator: WorldGenerator::new(seed),
+            generator: WorldGenerator::new(seed, options.identification),
+            rng: Pcg32::new(seed, GAMEPLAY_RNG_STREAM),
+            context: TurnContext::new(Pcg32::new(seed, COMBAT_RNG_STREAM), options),
+            identification,
             level: 0,
             rooms: Vec::new(),
             discovered_rooms: Vec::new(),
+            doorways: Vec::new(),
             entities: Vec::new(),
             ais: Vec::new(),
             previous_round_entities: None,
+            animation_steps: Vec::new(),
+            animation_step: None,
+            animation_progress: 0.0,
+            combat_events: Vec::new(),
+            animated_steps: NO_ANIMATED_STEPS,
+            transitions: Vec::new(),
+            sounds: Vec::new(),
+            sounds_to_play: Vec::new(),
+            trap_triggers: Vec::new(),
             animation_timer: 0.0,
+            tile_size: TILE_SIZE,
+            camera: None,
         };
         world.spawn(PROTO_PLAYER.clone_at(0, 0));
         world.generate_next_level();
@@ -112,11 +265,21 @@ impl World {
         player.position.y = player_origin.1 + 3;
 
         // Add in the new stage
+        self.doorways.clear();
         for new_entity in entities.into_iter() {
+            if new_entity.door || new_entity.hidden_door.is_some() {
+                self.doorways.push(new_entity.position.clone());
+            }
             self.spawn(new_entity);
         }
         self.previous_round_entities = Some(self.entities.clone());
+        self.animation_steps.clear();
+        self.combat_events.clear();
+        self.transitions.clear();
+        self.sounds.clear();
+        self.record_animation_step();
         self.rooms = rooms;
+        self.camera = None;
 
         self.discovered_rooms.truncate(0);
         if let Some(player_room) = self.rooms.iter().find(|room| {
@@ -127,14 +290,21 @@ impl World {
         }
     }
 
-    pub fn spawn(&mut self, entity: Entity) -> usize {
+    pub fn spawn(&mut self, mut entity: Entity) -> usize {
+        if let Some(item) = entity.drop {
+            entity.sprite = self.identification.sprite(item);
+        }
         let index = if let Some(index) = self.entities.iter().position(|e| e.marked_for_death) {
+            self.queue_despawn(index);
             if let Some(ai) = &entity.ai {
                 self.ais[index] = Some(ai.create_ai());
             } else {
                 self.ais[index] = None;
             }
             self.entities[index] = entity.clone();
+            for step in &mut self.animation_steps {
+                step[index] = (entity.position.clone(), entity.is_alive());
+            }
             if let Some(previous_round_entities) = &mut self.previous_round_entities {
                 previous_round_entities[index] = entity;
             }
@@ -146,23 +316,92 @@ impl World {
                 self.ais.push(None);
             }
             self.entities.push(entity.clone());
+            for step in &mut self.animation_steps {
+                step.push((entity.position.clone(), entity.is_alive()));
+            }
             if let Some(previous_round_entities) = &mut self.previous_round_entities {
                 previous_round_entities.push(entity);
             }
             self.entities.len() - 1
         };
+        let step = self.animation_steps.len();
+        self.transitions
+            .push((step, EntityTransition::Spawn(index)));
         crate::ui::DebugState::modify(|state| state.entity_count = self.entities.len());
         index
     }
 
+    /// Queues up the despawn transition of the entity at the index, if
+    /// it's been marked for death and it hasn't been queued up yet. It
+    /// plays in the next animation step to be recorded.
+    fn queue_despawn(&mut self, index: usize) {
+        let entity = &mut self.entities[index];
+        if !entity.marked_for_death || entity.animation.despawned {
+            return;
+        }
+        entity.animation.despawned = true;
+        let mut ghost = entity.clone();
+        let (opacity, rotation) = resting_animation(ghost.is_alive());
+        ghost.animation = Animation {
+            opacity: AnimationState {
+                current: opacity,
+                from: opacity,
+                to: opacity,
+            },
+            rotation: AnimationState {
+                current: rotation,
+                from: rotation,
+                to: rotation,
+            },
+            ..Animation::with_transitions(ghost.animation.spawn, ghost.animation.despawn)
+        };
+        let step = self.animation_steps.len();
+        self.transitions
+            .push((step, EntityTransition::Despawn(Box::new(ghost))));
+    }
+
     pub fn update(&mut self, action: PlayerAction, debug_mode: bool) {
+        if let PlayerAction::ChoosePerk(perk) = action {
+            self.choose_perk(perk);
+            return;
+        }
+
         // Huuuge clone, I know. See the docs for
         // `self.previous_round_entities`.
         self.previous_round_entities = Some(self.entities.clone());
+        self.trap_triggers.clear();
         self.animation_timer = 0.0;
+        self.animation_step = None;
+        self.animation_steps.clear();
+        self.combat_events.clear();
+        self.animated_steps = NO_ANIMATED_STEPS;
+        self.transitions.clear();
+        self.sounds.clear();
+        self.record_animation_step();
 
         // Update player
         self.update_player(action, debug_mode);
+        for new_entity in self.resolve_knockbacks() {
+            self.spawn(new_entity);
+        }
+        self.identify_carried_equipment();
+        if let Some(spawns) = self.trigger_traps(0) {
+            for new_entity in spawns {
+                self.spawn(new_entity);
+            }
+        }
+
+        // Reveal hidden traps next to the player
+        let &Position { x, y } = &self.entities[0].position;
+        for entity in self
+            .entities
+            .iter_mut()
+            .filter(|e| (e.position.x - x).abs() <= 1 && (e.position.y - y).abs() <= 1)
+        {
+            if let Some(trap) = &mut entity.trap {
+                trap.hidden = false;
+            }
+        }
 
         // Update discovered rooms
         let player_room = self
@@ -179,33 +418,185 @@ impl World {
                 self.discovered_rooms.push(player_room.clone());
             }
         }
+        self.record_animation_step();
+
+        // While hasted, the player gets every other turn for free
+        let haste_free_turn = self.entities[0]
+            .status_effect(StatusKind::Haste)
+            .map(|haste| haste.duration % 2 == 1)
+            .unwrap_or(false);
 
-        let mut stopwatch_timestop = false;
+        let mut turn_effects = TurnEffects::default();
         if let Some(inventory) = &mut self.entities[0].inventory {
             for item in inventory.iter_mut() {
-                match item {
-                    Item::Stopwatch(current_tick) => {
-                        stopwatch_timestop = *current_tick;
-                        *current_tick = !*current_tick;
-                    }
-                    _ => {}
+                if let Some(next_item) = item.effect().on_turn(&mut turn_effects) {
+                    *item = next_item;
                 }
             }
         }
 
-        if !stopwatch_timestop {
+        if !turn_effects.time_stopped && !haste_free_turn {
             // Update the rest of the entities, in order
             let mut i = 1;
             loop {
                 self.update_at_index(i, &player_room);
+                self.record_animation_step();
                 i += 1;
                 if i == self.entities.len() {
                     break;
                 }
             }
-        } else {
-            // TODO: Play a ticking sound to indicate the stopwatch stopping time?
+        } else if turn_effects.time_stopped {
+            self.context.sounds.push(Sound::StopwatchTick);
+        }
+
+        self.award_experience();
+        self.drop_loot();
+        self.record_animation_step();
+    }
+
+    /// Records where everyone is and who's alive as a step of the
+    /// round's animation, along with the combat events, the entities
+    /// appearing and disappearing, and the sounds since the last
+    /// step, if anything has changed.
+    fn record_animation_step(&mut self) {
+        for i in 0..self.entities.len() {
+            self.queue_despawn(i);
+        }
+        let step: Vec<(Position, bool)> = self
+            .entities
+            .iter()
+            .map(|e| (e.position.clone(), e.is_alive()))
+            .collect();
+        let step_index = self.animation_steps.len();
+        let has_events = self.entities.iter().any(|e| !e.animation.events.is_empty());
+        let has_transitions = self.transitions.iter().any(|&(s, _)| s == step_index);
+        let has_sounds = !self.context.sounds.is_empty();
+        if self.animation_steps.last() == Some(&step)
+            && !has_events
+            && !has_transitions
+            && !has_sounds
+        {
+            return;
+        }
+
+        let mut sounds = std::mem::take(&mut self.context.sounds);
+        if let Some(previous_step) = self.animation_steps.last() {
+            let died = |(&(_, was_alive), &(_, alive)): (&(Position, bool), &(Position, bool))| {
+                was_alive && !alive
+            };
+            if previous_step.iter().zip(&step).any(died) {
+                sounds.push(Sound::Death);
+            }
+        }
+        self.animation_steps.push(step);
+        for (i, entity) in self.entities.iter_mut().enumerate() {
+            for event in entity.animation.events.take() {
+                match event {
+                    CombatEvent::Attack(_, _) => sounds.push(Sound::Attack),
+                    CombatEvent::Damage(_) | CombatEvent::Poison(_) => sounds.push(Sound::Hit),
+                    CombatEvent::Heal(_) => {}
+                }
+                self.combat_events.push((step_index, i, event));
+            }
+        }
+        // A sound playing many times over at once would only be louder
+        for sound in sounds {
+            if !self.sounds.contains(&(step_index, sound)) {
+                self.sounds.push((step_index, sound));
+            }
+        }
+    }
+
+    /// Gives the player the XP of everything that died this round.
+    fn award_experience(&mut self) {
+        let mut xp = 0;
+        for entity in self.entities[1..].iter_mut().filter(|e| !e.is_alive()) {
+            xp += entity.xp_value;
+            entity.xp_value = 0;
+        }
+        if xp == 0 {
+            return;
+        }
+        if let Some(experience) = &mut self.entities[0].experience {
+            if experience.gain(xp) > 0 {
+                let message = format!("You reached level {}!", experience.level);
+                self.context.log.add(message);
+            }
+        }
+    }
+
+    /// Identifies the equipment the player has picked up.
+    fn identify_carried_equipment(&mut self) {
+        for item in carried_items(&self.entities[0]) {
+            self.identify(item);
+        }
+    }
+
+    /// Identifies the item, and tells the player what it turned out
+    /// to be.
+    fn identify(&mut self, item: Item) {
+        let unknown_name = self.identification.name(item);
+        if self.identification.identify(item) {
+            self.context.log.add(format!(
+                "The {} is a {}.",
+                unknown_name.to_lowercase(),
+                item.name().to_lowercase()
+            ));
+        }
+    }
+
+    /// The item's name, as far as the player knows.
+    pub fn item_name(&self, item: Item) -> &'static str {
+        self.identification.name(item)
+    }
+
+    /// The item's description, as far as the player knows.
+    pub fn item_description(&self, item: Item) -> &'static str {
+        self.identification.description(item)
+    }
+
+    /// Drops the gold and loot of everything that died this round
+    /// where they fell.
+    fn drop_loot(&mut self) {
+        let (player, others) = self.entities.split_at_mut(1);
+        let player_inventory = player[0].inventory.as_ref().unwrap();
+        let mut drops = Vec::new();
+        for entity in others.iter_mut().filter(|e| !e.is_alive()) {
+            let &Position { x, y } = &entity.position;
+            if entity.gold_value > 0 {
+                let amount = 1 + (self.rng.next_u32() % entity.gold_value as u32) as i32;
+                drops.push(pickup_entity(Item::Gold(amount)).clone_at(x, y));
+                entity.gold_value = 0;
+            }
+            if let Some(table) = entity.loot.take() {
+                if let Some(item) = table.roll(&mut self.rng, self.level, player_inventory) {
+                    drops.push(pickup_entity(item).clone_at(x, y));
+                }
+            }
         }
+        for drop in drops {
+            self.spawn(drop);
+        }
+    }
+
+    fn choose_perk(&mut self, perk: Perk) {
+        let player = &mut self.entities[0];
+        if let Some(experience) = &mut player.experience {
+            if experience.unspent_perks > 0 {
+                experience.unspent_perks -= 1;
+                perk.apply(player);
+            }
+        }
+    }
+
+    /// Returns true if the player has leveled up, and hasn't picked a
+    /// perk for it yet.
+    pub fn has_unspent_perks(&self) -> bool {
+        self.entities[0]
+            .experience
+            .iter()
+            .any(|experience| experience.unspent_perks > 0)
     }
 
     /// Updates the entity at index `i` and if that entity spawns new
@@ -221,18 +612,184 @@ impl World {
         };
         if can_act && player_in_room {
             if let Some(ai) = &mut self.ais[i] {
-                let spawns = ai.update(i, &mut self.entities);
+                let spawns = ai.update(i, &mut self.entities, &mut self.context);
                 if let Some(spawns) = spawns {
-                    for new_entity in spawns {
-                        let index = self.spawn(new_entity);
-                        if index < i {
-                            self.update_at_index(index, player_room);
+                    self.spawn_during_update(i, spawns, player_room);
+                }
+                let spawns = self.resolve_knockbacks();
+                if !spawns.is_empty() {
+                    self.spawn_during_update(i, spawns, player_room);
+                }
+            }
+            if let Some(spawns) = self.trigger_traps(i) {
+                self.spawn_during_update(i, spawns, player_room);
+            }
+        }
+        self.entities[i].tick_status_effects();
+    }
+
+    /// Spawns entities created by the entity at index `i`, and
+    /// updates the ones that end up with an index less than `i`, so
+    /// they don't miss their turn.
+    fn spawn_during_update(&mut self, i: usize, spawns: Vec<Entity>, player_room: &Option<Room>) {
+        for new_entity in spawns {
+            let index = self.spawn(new_entity);
+            if index < i {
+                self.update_at_index(index, player_room);
+            }
+        }
+    }
+
+    /// Springs the traps the entity at index `i` has stepped on this
+    /// round. Returns the entities the traps spawn, if any.
+    fn trigger_traps(&mut self, i: usize) -> Option<Vec<Entity>> {
+        let moved = match &self.previous_round_entities {
+            Some(previous) => previous[i].position != self.entities[i].position,
+            None => false,
+        };
+        if !moved || self.entities[i].health.is_none() || !self.entities[i].is_alive() {
+            return None;
+        }
+        let position = self.entities[i].position.clone();
+        if self.trap_triggers.contains(&(i, position.clone())) {
+            return None;
+        }
+        self.trap_triggers.push((i, position.clone()));
+
+        let mut spawns = None;
+        for trap_index in 0..self.entities.len() {
+            let trap_entity = &mut self.entities[trap_index];
+            if trap_entity.position != position || trap_entity.marked_for_death {
+                continue;
+            }
+            let kind = if let Some(trap) = &mut trap_entity.trap {
+                trap.hidden = false;
+                trap.kind
+            } else {
+                continue;
+            };
+
+            match kind {
+                TrapKind::Spikes => {
+                    let (trap, others) = split_entities(trap_index, &mut self.entities);
+                    attack_direction(trap, others, 0, 0, &mut self.context);
+                }
+                TrapKind::PressurePlate => {
+                    // The plate only has enough fuel for one blaze
+                    self.entities[trap_index].marked_for_death = true;
+                    let mut flames = Vec::with_capacity(9);
+                    for y in -1..=1 {
+                        for x in -1..=1 {
+                            let (x, y) = (position.x + x, position.y + y);
+                            // Creatures get burned, but walls and
+                            // closed doors keep the flames out
+                            let blocked = self.entities.iter().any(|e| {
+                                e.position.x == x
+                                    && e.position.y == y
+                                    && e.denies_movement
+                                    && e.health.is_none()
+                                    && e.is_alive()
+                            });
+                            if !blocked {
+                                flames.push(PROTO_FLAME.clone_at(x, y));
+                            }
                         }
                     }
+                    self.context.sounds.push(Sound::Flame);
+                    spawns = Some(flames);
+                }
+                TrapKind::FireVent => {}
+                TrapKind::Cobweb => {
+                    self.entities[trap_index].marked_for_death = true;
+                    let slow = StatusEffect::new(StatusKind::Slow, COBWEB_SLOW_DURATION);
+                    self.entities[i].apply_status_effect(slow);
                 }
             }
         }
-        self.entities[i].tick_status_effects();
+        spawns
+    }
+
+    /// Pushes the entities knocked back this turn, one tile at a
+    /// time, until they've gone the distance or hit something. Traps
+    /// are sprung along the way. Returns the entities spawned by the
+    /// traps.
+    fn resolve_knockbacks(&mut self) -> Vec<Entity> {
+        let mut spawns = Vec::new();
+        for knockback in std::mem::take(&mut self.context.knockbacks) {
+            let Knockback {
+                x,
+                y,
+                xd,
+                yd,
+                distance,
+            } = knockback;
+            let index = self.entities.iter().position(|e| {
+                e.position.x == x && e.position.y == y && e.health.is_some() && e.is_alive()
+            });
+            let index = match index {
+                Some(index) => index,
+                None => continue,
+            };
+
+            for _ in 0..distance {
+                let (target, others) = split_entities(index, &mut self.entities);
+                if !move_entity(&mut target.position, others, xd, yd) {
+                    self.collide(index, xd, yd);
+                    break;
+                }
+                spawns.extend(self.trigger_traps(index).into_iter().flatten());
+                if !self.entities[index].is_alive() {
+                    break;
+                }
+            }
+        }
+        spawns
+    }
+
+    /// Deals collision damage to the entity at `index` and to
+    /// whatever it was knocked into.
+    fn collide(&mut self, index: usize, xd: i32, yd: i32) {
+        let &Position { x, y } = &self.entities[index].position;
+        let name = self.entities[index].name;
+        take_damage(&mut self.entities[index], KNOCKBACK_COLLISION_DAMAGE);
+
+        let obstacle = self.entities.iter_mut().find(|e| {
+            e.position.x == x + xd && e.position.y == y + yd && e.denies_movement && e.is_alive()
+        });
+        let obstacle_name = match obstacle {
+            Some(obstacle) => {
+                take_damage(obstacle, KNOCKBACK_COLLISION_DAMAGE);
+                obstacle.name
+            }
+            None => "Wall",
+        };
+        self.context.log.add(format!(
+            "{} slams into {} for {}",
+            name, obstacle_name, KNOCKBACK_COLLISION_DAMAGE
+        ));
+    }
+
+    /// Returns the sounds that should start playing, as the steps
+    /// they happened in have started animating.
+    pub fn take_sounds(&mut self) -> Vec<Sound> {
+        std::mem::take(&mut self.sounds_to_play)
+    }
+
+    /// Returns true if the dragon is alive and in the same room as the
+    /// player.
+    pub fn is_facing_dragon(&self) -> bool {
+        let player = &self.entities[0].position;
+        let dragon = self
+            .entities
+            .iter()
+            .find(|e| e.dragon && !e.marked_for_death);
+        match dragon {
+            Some(dragon) if dragon.is_alive() => self.rooms.iter().any(|room| {
+                room.contains(player.x, player.y)
+                    && room.contains(dragon.position.x, dragon.position.y)
+            }),
+            _ => false,
+        }
     }
 
     pub fn is_dragon_dead(&self) -> bool {
@@ -243,10 +800,106 @@ impl World {
         }
     }
 
+    pub fn options(&self) -> &GameOptions {
+        &self.context.options
+    }
+
+    /// Changes the options in the middle of a run. Identification
+    /// stays as it was when the run started, since the items have
+    /// already been generated with or without it.
+    pub fn set_options(&mut self, options: &GameOptions) {
+        self.context.options = GameOptions {
+            identification: self.context.options.identification,
+            ..options.clone()
+        };
+    }
+
+    /// Sets the size the tiles are drawn at, in pixels, as long as the
+    /// player's room fits in the view with it.
+    pub fn set_tile_size(&mut self, tile_size: f32) {
+        self.tile_size = tile_size;
+    }
+
+    /// Returns the last `count` messages, oldest first.
+    pub fn recent_messages(&self, count: usize) -> impl Iterator<Item = &String> {
+        self.context.log.recent(count)
+    }
+
     pub fn player(&self) -> &Entity {
         &self.entities[0]
     }
 
+    /// The item the player is standing on, if any. Wares that
+    /// haven't been bought yet don't count.
+    pub fn pickup_under_player(&self) -> Option<Item> {
+        let player = &self.entities[0];
+        self.entities()
+            .iter()
+            .filter(|e| e.position == player.position && !e.marked_for_death)
+            .filter(|e| e.price.is_none())
+            .find_map(|e| e.drop)
+    }
+
+    /// The ware the player is standing on and its price, if any.
+    pub fn ware_under_player(&self) -> Option<(Item, i32)> {
+        let player = &self.entities[0];
+        self.entities()
+            .iter()
+            .filter(|e| e.position == player.position && !e.marked_for_death)
+            .find_map(|e| match (e.drop, e.price) {
+                (Some(item), Some(price)) => Some((item, price)),
+                _ => None,
+            })
+    }
+
+    /// Returns true if picking up the item under the player needs
+    /// them to choose which item to swap out for it.
+    pub fn pickup_needs_free_slot(&self) -> bool {
+        let inventory_full = self.entities[0].inventory.iter().any(|inv| inv.is_full());
+        match self.pickup_under_player() {
+            Some(Item::Apple) | Some(Item::Gold(_)) | Some(Item::Consumable(_)) | None => false,
+            Some(_) => inventory_full,
+        }
+    }
+
+    /// Returns the position of the closest living monster within
+    /// throwing range, for the targeting cursor to start on.
+    pub fn nearest_throw_target(&self) -> Option<(i32, i32)> {
+        let &Position { x, y } = &self.entities[0].position;
+        self.entities[1..]
+            .iter()
+            .filter(|e| e.ai.is_some() && e.health.is_some() && e.is_alive())
+            .map(|e| (e.position.x, e.position.y))
+            .filter(|&(ex, ey)| (ex - x).abs().max((ey - y).abs()) <= THROW_RANGE)
+            .min_by_key(|&(ex, ey)| (ex - x).abs() + (ey - y).abs())
+    }
+
+    /// The entities the player can see at (`x`, `y`), along with what
+    /// their AIs are up to.
+    pub fn look_at(&self, x: i32, y: i32) -> Vec<(&Entity, Option<&'static str>)> {
+        let player_room = self.rooms.iter().find(|room| {
+            let &Position { x, y } = &self.entities[0].position;
+            room.contains(x, y)
+        });
+        let in_player_room = player_room.iter().any(|room| room.contains(x, y));
+        if !self.discovered_rooms.iter().any(|room| room.contains(x, y)) {
+            return Vec::new();
+        }
+        self.entities
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.position.x == x && e.position.y == y)
+            .filter(|(_, e)| !e.marked_for_death && !e.is_hidden())
+            .filter(|(_, e)| in_player_room || !e.visibility_affected())
+            .map(|(i, e)| {
+                let state = self.ais[i]
+                    .as_ref()
+                    .and_then(|ai| ai.state(i, &self.entities));
+                (e, state)
+            })
+            .collect()
+    }
+
     /// Excluding the player, get the player with World::player.
     pub fn entities(&self) -> &[Entity] {
         &self.entities[1..]
@@ -254,22 +907,74 @@ impl World {
 
     fn update_player(&mut self, action: PlayerAction, debug_mode: bool) {
         if self.entities[0].can_act() {
+            let diagonal = self.context.options.diagonal_movement;
             let mut move_direction = None;
             match action {
                 PlayerAction::MoveUp => move_direction = Some((0, -1)),
                 PlayerAction::MoveDown => move_direction = Some((0, 1)),
                 PlayerAction::MoveRight => move_direction = Some((1, 0)),
                 PlayerAction::MoveLeft => move_direction = Some((-1, 0)),
-                PlayerAction::Pickup => {
+                PlayerAction::MoveUpLeft if diagonal => move_direction = Some((-1, -1)),
+                PlayerAction::MoveUpRight if diagonal => move_direction = Some((1, -1)),
+                PlayerAction::MoveDownLeft if diagonal => move_direction = Some((-1, 1)),
+                PlayerAction::MoveDownRight if diagonal => move_direction = Some((1, 1)),
+                PlayerAction::MoveUpLeft
+                | PlayerAction::MoveUpRight
+                | PlayerAction::MoveDownLeft
+                | PlayerAction::MoveDownRight => {}
+                PlayerAction::Pickup | PlayerAction::Swap(_) => {
+                    let slot = match action {
+                        PlayerAction::Swap(slot) => Some(slot),
+                        _ => None,
+                    };
                     let (player, others) = split_entities(0, &mut self.entities);
-                    pickup(
-                        &player.position,
-                        player.health.as_mut().unwrap(),
-                        player.inventory.as_mut().unwrap(),
-                        others,
-                    );
+                    pickup(player, others, slot, &mut self.context);
+                }
+                PlayerAction::Buy => self.buy(),
+                PlayerAction::ShieldBash(xd, yd) => self.shield_bash(xd, yd),
+                PlayerAction::Use(slot) => {
+                    let inventory = self.entities[0].inventory.as_mut().unwrap();
+                    let throwable = inventory.belt.get(slot).iter().any(|c| c.is_throwable());
+                    if !throwable {
+                        if let Some(consumable) = inventory.take_consumable(slot) {
+                            self.use_consumable(consumable);
+                        }
+                    }
+                }
+                PlayerAction::Throw(source, x, y) => {
+                    let inventory = self.entities[0].inventory.as_mut().unwrap();
+                    let item = match source {
+                        ThrowSource::Inventory(slot) => inventory.remove_item(slot),
+                        ThrowSource::Belt(slot) => {
+                            inventory.take_consumable(slot).map(Item::Consumable)
+                        }
+                    };
+                    if let Some(item) = item {
+                        self.throw(item, x, y);
+                    }
+                }
+                PlayerAction::Drop(slot) => {
+                    let player = &mut self.entities[0];
+                    let dropped = player.inventory.as_mut().unwrap().remove_item(slot);
+                    if let Some(item) = dropped {
+                        let &Position { x, y } = &player.position;
+                        self.spawn(pickup_entity(item).clone_at(x, y));
+                    }
                 }
+                // Handled in World::update, as it doesn't take a turn
+                PlayerAction::ChoosePerk(_) => {}
                 PlayerAction::Wait => {
+                    // Waiting around gives the player time to search
+                    // the walls nearby for hidden doors.
+                    let &Position { x, y } = &self.entities[0].position;
+                    for entity in self
+                        .entities
+                        .iter_mut()
+                        .filter(|e| (e.position.x - x).abs() <= 1 && (e.position.y - y).abs() <= 1)
+                    {
+                        entity.search();
+                    }
+
                     if debug_mode {
                         if let Some(health) = &mut self.entities[0].health {
                             health.current += 1;
@@ -289,6 +994,8 @@ impl World {
                         .is_some();
                     if on_stairs {
                         self.generate_next_level();
+                        self.context.sounds.push(Sound::Stairs);
+                        self.record_animation_step();
                         return;
                     }
                 }
@@ -297,8 +1004,13 @@ impl World {
             if let Some((xd, yd)) = move_direction {
                 let (player, others) = split_entities(0, &mut self.entities);
                 let moved = move_entity(&mut player.position, others, xd, yd);
+                let player = &self.entities[0];
+                let corner_cut = self
+                    .entities
+                    .iter()
+                    .any(|e| cuts_corner(&player.position, xd, yd, e));
 
-                if !moved {
+                if !moved && !corner_cut {
                     let player = &self.entities[0];
                     if let Some(door_index) = &self.entities.iter().position(|entity| {
                         entity.position.x == player.position.x + xd
@@ -306,20 +1018,23 @@ impl World {
                             && entity.door
                     }) {
                         self.entities[*door_index].marked_for_death = true;
-                        // TODO: Play door opening sound
-                        // TODO: Animate door opening
+                        self.context.sounds.push(Sound::Door);
                     }
 
+                    // Bumping into walls might reveal a hidden door
+                    let player = &self.entities[0];
+                    let (target_x, target_y) = (player.position.x + xd, player.position.y + yd);
+                    for entity in self
+                        .entities
+                        .iter_mut()
+                        .filter(|e| e.position.x == target_x && e.position.y == target_y)
+                    {
+                        entity.search();
+                    }
+
+                    self.provoke_at(target_x, target_y);
                     let (player, others) = split_entities(0, &mut self.entities);
-                    attack_direction(
-                        &player.position,
-                        player.damage.as_ref().unwrap(),
-                        &mut player.health,
-                        &player.inventory,
-                        others,
-                        xd,
-                        yd,
-                    )
+                    attack_direction(player, others, xd, yd, &mut self.context)
                 }
             }
         }
@@ -327,58 +1042,297 @@ impl World {
         self.entities[0].tick_status_effects();
     }
 
-    pub fn animate(&mut self, delta_seconds: f32, round_duration: f32) {
-        let previous_round_entities = match &self.previous_round_entities {
-            Some(entities) => entities,
+    /// Throws the item from the player towards (`target_x`,
+    /// `target_y`). It flies until it hits something or has travelled
+    /// THROW_RANGE tiles, and then falls on the floor.
+    fn throw(&mut self, item: Item, target_x: i32, target_y: i32) {
+        let &Position { x, y } = &self.entities[0].position;
+        let mut landing = (x, y);
+        for (x, y) in line(x, y, target_x, target_y)
+            .into_iter()
+            .skip(1)
+            .take(THROW_RANGE as usize)
+        {
+            let obstacle = self.entities.iter().find(|e| {
+                e.position.x == x && e.position.y == y && e.denies_movement && e.is_alive()
+            });
+            match obstacle {
+                Some(obstacle) if obstacle.health.is_some() => {
+                    landing = (x, y);
+                    self.provoke_at(x, y);
+                    // The projectile has no inventory, so the throw
+                    // damage isn't modified by the item's own
+                    // modify_damage, but its on-hit effects still apply
+                    let mut projectile = Entity {
+                        name: self.identification.name(item),
+                        damage: Some(Damage(item.throw_damage())),
+                        combat_stats: self.entities[0].combat_stats,
+                        ..pickup_entity(item).clone_at(x, y)
+                    };
+                    let others = self.entities.iter_mut();
+                    let context = &mut self.context;
+                    attack_direction_with_items(&mut projectile, others, 0, 0, &[item], context);
+                    break;
+                }
+                Some(_) => break,
+                None => landing = (x, y),
+            }
+        }
+        self.spawn(pickup_entity(item).clone_at(landing.0, landing.1));
+    }
+
+    /// Lets the AIs of the living entities at (`x`, `y`) know that
+    /// the player is attacking them.
+    fn provoke_at(&mut self, x: i32, y: i32) {
+        let context = &mut self.context;
+        for (entity, ai) in self.entities.iter().zip(self.ais.iter_mut()) {
+            if entity.position.x == x && entity.position.y == y && entity.is_alive() {
+                if let Some(ai) = ai {
+                    ai.provoke(context);
+                }
+            }
+        }
+    }
+
+    fn shield_bash(&mut self, xd: i32, yd: i32) {
+        let player = &self.entities[0];
+        let has_shield = player
+            .inventory
+            .iter()
+            .any(|inv| inv.has_item(Item::Shield));
+        let diagonal = xd != 0 && yd != 0;
+        if !has_shield
+            || xd.abs() > 1
+            || yd.abs() > 1
+            || (diagonal && !self.context.options.diagonal_movement)
+            || self
+                .entities
+                .iter()
+                .any(|e| cuts_corner(&player.position, xd, yd, e))
+        {
+            return;
+        }
+
+        // The shield hits on its own, without the effects of the
+        // player's other items
+        let mut shield = Entity {
+            damage: Some(Damage(SHIELD_BASH_DAMAGE)),
+            knockback: SHIELD_BASH_KNOCKBACK,
+            inventory: None,
+            ..player.clone()
+        };
+        let (target_x, target_y) = (player.position.x + xd, player.position.y + yd);
+        self.provoke_at(target_x, target_y);
+        let (_, others) = split_entities(0, &mut self.entities);
+        attack_direction(&mut shield, others, xd, yd, &mut self.context);
+    }
+
+    fn buy(&mut self) {
+        let position = self.entities[0].position.clone();
+        let ware_index = self
+            .entities
+            .iter()
+            .position(|e| e.position == position && !e.marked_for_death && e.price.is_some());
+        let ware_index = match ware_index {
+            Some(index) => index,
             None => return,
         };
-        let progress = if round_duration > 0.0 {
-            (self.animation_timer / round_duration).min(1.0)
-        } else {
-            1.0
+        let price = self.entities[ware_index].price.unwrap_or(0);
+        let name = match self.entities[ware_index].drop {
+            Some(item) => self.identification.name(item).to_lowercase(),
+            None => return,
         };
 
-        // TODO: Make the player animate before everyone else.
-
-        if self.animation_timer == 0.0 {
-            // First frame of the current round, update everything
-            // accordingly.
-            for i in 0..self.entities.len() {
-                let current = &mut self.entities[i];
-                let previous = &previous_round_entities[i];
-                let (xd, yd) = (
-                    current.position.x - previous.position.x,
-                    current.position.y - previous.position.y,
-                );
-                current.animation.x.from = current.animation.x.current - xd as f32;
-                current.animation.y.from = current.animation.y.current - yd as f32;
-
-                let alive_opacity = 1.0;
-                let alive_rotation = 0.0;
-                let dead_opacity = 0.2;
-                let dead_rotation = std::f32::consts::PI * 0.4;
-                if current.is_alive() {
-                    current.animation.opacity.from = alive_opacity;
-                    current.animation.opacity.to = alive_opacity;
-                    current.animation.rotation.from = alive_rotation;
-                    current.animation.rotation.to = alive_rotation;
-                    if !previous.is_alive() {
-                        // Just revived!
-                        current.animation.opacity.from = dead_opacity;
-                        current.animation.rotation.from = dead_rotation;
+        let inventory = self.entities[0].inventory.as_mut().unwrap();
+        if inventory.gold < price {
+            let message = format!("You can't afford the {} ({} gold).", name, price);
+            self.context.log.add(message);
+            return;
+        }
+        inventory.gold -= price;
+        self.entities[ware_index].price = None;
+        let message = format!("You bought the {} for {} gold.", name, price);
+        self.context.log.add(message);
+
+        let (player, others) = split_entities(0, &mut self.entities);
+        pickup(player, others, None, &mut self.context);
+    }
+
+    fn use_consumable(&mut self, consumable: Consumable) {
+        let &Position { x, y } = &self.entities[0].position;
+        self.identify(Item::Consumable(consumable));
+        match consumable {
+            Consumable::HealingPotion => self.entities[0].heal(HEALING_POTION_HEALTH),
+            Consumable::HastePotion => {
+                let haste = StatusEffect::new(StatusKind::Haste, HASTE_DURATION);
+                self.entities[0].apply_status_effect(haste);
+            }
+            Consumable::StrengthPotion => {
+                let strength = StatusEffect::new(StatusKind::Strength, STRENGTH_DURATION);
+                self.entities[0].apply_status_effect(strength);
+            }
+            Consumable::RegenerationPotion => {
+                let regeneration =
+                    StatusEffect::new(StatusKind::Regeneration, REGENERATION_DURATION);
+                self.entities[0].apply_status_effect(regeneration);
+            }
+            Consumable::TeleportScroll => {
+                let other_rooms: Vec<&Room> = self
+                    .rooms
+                    .iter()
+                    .filter(|room| !room.contains(x, y))
+                    .collect();
+                let mut teleported = false;
+                for _ in 0..50 {
+                    if other_rooms.is_empty() {
+                        break;
                     }
-                } else {
-                    current.animation.opacity.from = dead_opacity;
-                    current.animation.opacity.to = dead_opacity;
-                    current.animation.rotation.from = dead_rotation;
-                    current.animation.rotation.to = dead_rotation;
-                    if previous.is_alive() {
-                        // Just died!
-                        current.animation.opacity.from = alive_opacity;
-                        current.animation.rotation.from = alive_rotation;
+                    let room = other_rooms[self.rng.next_u32() as usize % other_rooms.len()];
+                    let x = room.x + 1 + (self.rng.next_u32() % (room.width - 2) as u32) as i32;
+                    let y = room.y + 1 + (self.rng.next_u32() % (room.height - 2) as u32) as i32;
+                    let occupied = self.entities.iter().any(|e| {
+                        e.position.x == x
+                            && e.position.y == y
+                            && !e.marked_for_death
+                            && ((e.denies_movement && e.is_alive()) || e.trap.is_some())
+                    });
+                    if !occupied {
+                        self.entities[0].position = Position { x, y };
+                        teleported = true;
+                        break;
+                    }
+                }
+                if !teleported {
+                    let message = String::from("The scroll crumbles, but nothing happens.");
+                    self.context.log.add(message);
+                }
+            }
+            Consumable::MappingScroll => {
+                self.discovered_rooms = self.rooms.clone();
+                for entity in &mut self.entities {
+                    if let Some(trap) = &mut entity.trap {
+                        trap.hidden = false;
                     }
+                    if entity.hidden_door.is_some() {
+                        entity.hidden_door = Some(1);
+                        entity.search();
+                    }
+                }
+            }
+            Consumable::IdentifyScroll => {
+                let player = &self.entities[0];
+                let mut items = carried_items(player);
+                if let Some(inventory) = &player.inventory {
+                    items.extend(inventory.belt.iter().map(|&c| Item::Consumable(c)));
+                }
+                if let Some(room) = self.rooms.iter().find(|room| room.contains(x, y)) {
+                    items.extend(
+                        self.entities
+                            .iter()
+                            .filter(|e| room.contains(e.position.x, e.position.y))
+                            .filter(|e| !e.marked_for_death)
+                            .filter_map(|e| e.drop),
+                    );
+                }
+                for item in items {
+                    self.identify(item);
+                }
+            }
+            // Throwing knives are thrown, see World::throw.
+            Consumable::ThrowingKnife => {}
+            Consumable::FearScroll => {
+                if let Some(room) = self.rooms.iter().find(|room| room.contains(x, y)) {
+                    for entity in self.entities[1..].iter_mut().filter(|e| {
+                        e.ai.is_some() && e.is_alive() && room.contains(e.position.x, e.position.y)
+                    }) {
+                        entity.apply_status_effect(StatusEffect::new(
+                            StatusKind::Fear,
+                            FEAR_DURATION,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Animates the round. With `sequenced`, each step of the round
+    /// plays after the previous one, so the player moves first and
+    /// the monsters then react one by one. Otherwise everything moves
+    /// at once.
+    pub fn animate(&mut self, delta_seconds: f32, round_duration: f32, sequenced: bool) {
+        let step_count = self.animation_steps.len();
+        if step_count == 0 {
+            return;
+        }
+        let steps: Vec<usize> = if sequenced && step_count > 1 {
+            (1..step_count).collect()
+        } else {
+            vec![step_count - 1]
+        };
+        let (step, progress) = if round_duration > 0.0 {
+            let elapsed = self.animation_timer / round_duration;
+            let step = (elapsed as usize).min(steps.len() - 1);
+            (step, (elapsed - step as f32).min(1.0))
+        } else {
+            (steps.len() - 1, 1.0)
+        };
+        self.animation_progress = progress;
+
+        if self.animation_step.is_none() {
+            // First frame of the current round: the animations are
+            // relative to the entities' positions, which have just
+            // changed, so move them along.
+            for (entity, (start, _)) in self.entities.iter_mut().zip(&self.animation_steps[0]) {
+                let animation = &mut entity.animation;
+                animation.x.current -= (entity.position.x - start.x) as f32;
+                animation.y.current -= (entity.position.y - start.y) as f32;
+            }
+        }
+
+        if self.animation_step != Some(step) {
+            // First frame of the step, animate from wherever everyone
+            // is towards the positions after the step.
+            self.animation_step = Some(step);
+            let snapshot = &self.animation_steps[steps[step]];
+            for (entity, (position, alive)) in self.entities.iter_mut().zip(snapshot) {
+                let (opacity, rotation) = resting_animation(*alive);
+                let animation = &mut entity.animation;
+                animation.x.from = animation.x.current;
+                animation.x.to = (position.x - entity.position.x) as f32;
+                animation.y.from = animation.y.current;
+                animation.y.to = (position.y - entity.position.y) as f32;
+                animation.opacity.from = animation.opacity.current;
+                animation.opacity.to = opacity;
+                animation.rotation.from = animation.rotation.current;
+                animation.rotation.to = rotation;
+                animation.lunge = (0.0, 0.0);
+                animation.hurt = false;
+            }
+
+            // Everything that happened between the previous step and
+            // this one plays out during this one
+            let first = if step == 0 { 1 } else { steps[step - 1] + 1 };
+            let animated_steps = first..steps[step] + 1;
+            for (_, i, event) in self
+                .combat_events
+                .iter()
+                .filter(|(step, _, _)| animated_steps.contains(step))
+            {
+                let animation = &mut self.entities[*i].animation;
+                match *event {
+                    CombatEvent::Attack(xd, yd) => animation.lunge = (xd as f32, yd as f32),
+                    CombatEvent::Damage(_) => animation.hurt = true,
+                    CombatEvent::Poison(_) | CombatEvent::Heal(_) => {}
                 }
             }
+            for &(_, sound) in self
+                .sounds
+                .iter()
+                .filter(|(step, _)| animated_steps.contains(step))
+            {
+                self.sounds_to_play.push(sound);
+            }
+            self.animated_steps = animated_steps;
         }
 
         let clamped_lerp = |animation: &mut AnimationState<f32>, x: f32| {
@@ -388,35 +1342,170 @@ impl World {
                 (animation.from + (animation.to - animation.from) * x).min(animation.to)
             };
         };
+        // Lunges and flashes peak in the middle of the step
+        let bump = (progress * std::f32::consts::PI).sin();
         for animation in self.entities.iter_mut().map(|e| &mut e.animation) {
             clamped_lerp(&mut animation.x, progress);
             clamped_lerp(&mut animation.y, progress);
             clamped_lerp(&mut animation.rotation, progress);
             clamped_lerp(&mut animation.opacity, progress);
+            animation.x.current += animation.lunge.0 * LUNGE_DISTANCE * bump;
+            animation.y.current += animation.lunge.1 * LUNGE_DISTANCE * bump;
+            animation.flash = if animation.hurt { bump } else { 0.0 };
         }
 
         self.animation_timer += delta_seconds;
     }
 
+    /// Skips the rest of the round's animation, putting everyone
+    /// where they ended up. The sounds of the skipped steps are played
+    /// all at once.
+    pub fn skip_animation(&mut self) {
+        let skipped_steps = self.animated_steps.end..self.animation_steps.len();
+        for &(_, sound) in self
+            .sounds
+            .iter()
+            .filter(|(step, _)| skipped_steps.contains(step))
+        {
+            if !self.sounds_to_play.contains(&sound) {
+                self.sounds_to_play.push(sound);
+            }
+        }
+        self.sounds.clear();
+        for entity in &mut self.entities {
+            let (opacity, rotation) = resting_animation(entity.is_alive());
+            let animation = &mut entity.animation;
+            for (state, value) in &mut [
+                (&mut animation.x, 0.0),
+                (&mut animation.y, 0.0),
+                (&mut animation.opacity, opacity),
+                (&mut animation.rotation, rotation),
+            ] {
+                state.current = *value;
+                state.from = *value;
+                state.to = *value;
+            }
+            animation.lunge = (0.0, 0.0);
+            animation.hurt = false;
+            animation.flash = 0.0;
+        }
+        self.animation_steps.clear();
+        self.combat_events.clear();
+        self.animated_steps = NO_ANIMATED_STEPS;
+        self.transitions.clear();
+    }
+
+    /// How far into existence (0.0 - 1.0) something spawning in the
+    /// animation step is, at this point of the animation.
+    fn spawn_presence(&self, step: usize) -> f32 {
+        if step < self.animated_steps.start {
+            1.0
+        } else if step < self.animated_steps.end {
+            self.animation_progress
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the top-left corner of the tile (`x`, `y`) on the
+    /// screen, when the world is drawn in an area of the given size.
+    pub fn tile_to_screen(&self, x: i32, y: i32, width: f32, height: f32) -> (f32, f32) {
+        let tile_size = self.view_tile_size(width, height);
+        let (offset_x, offset_y) = self.camera_offset(width, height);
+        (
+            x as f32 * tile_size + offset_x,
+            y as f32 * tile_size + offset_y,
+        )
+    }
+
+    /// Returns the tile under the point (`x`, `y`) on the screen,
+    /// when the world is drawn in an area of the given size.
+    pub fn screen_to_tile(&self, x: f32, y: f32, width: f32, height: f32) -> (i32, i32) {
+        let tile_size = self.view_tile_size(width, height);
+        let (offset_x, offset_y) = self.camera_offset(width, height);
+        (
+            ((x - offset_x) / tile_size).floor() as i32,
+            ((y - offset_y) / tile_size).floor() as i32,
+        )
+    }
+
+    /// Returns the size the tiles are drawn at in an area of the given
+    /// size. It's the tile size from the settings, unless the player's
+    /// room wouldn't fit in the area with it, in which case the tiles
+    /// are shrunk so that it does.
+    pub fn view_tile_size(&self, width: f32, height: f32) -> f32 {
+        match self.fitting_tile_size(width, height) {
+            Some(fitting_tile_size) => self.tile_size.min(fitting_tile_size).max(1.0),
+            None => self.tile_size,
+        }
+    }
+
+    /// Returns the biggest tile size the player's room fits in an area
+    /// of the given size with, or None if the player isn't in a room.
+    pub fn fitting_tile_size(&self, width: f32, height: f32) -> Option<f32> {
+        let &Position { x, y } = &self.entities[0].position;
+        let room = self.rooms.iter().find(|room| room.contains(x, y))?;
+        let fitting_width = width / room.width as f32;
+        let fitting_height = height / room.height as f32;
+        Some(fitting_width.min(fitting_height).floor())
+    }
+
+    /// Moves the camera towards the player. With `smooth`, the camera
+    /// lags behind a little and catches up, otherwise it sticks to the
+    /// player.
+    pub fn move_camera(&mut self, delta_seconds: f32, smooth: bool) {
+        let (target_x, target_y) = self.player_focus();
+        self.camera = match self.camera {
+            Some((x, y)) if smooth => {
+                let t = 1.0 - (-CAMERA_FOLLOW_SPEED * delta_seconds).exp();
+                Some((x + (target_x - x) * t, y + (target_y - y) * t))
+            }
+            _ => Some((target_x, target_y)),
+        };
+    }
+
+    /// The center of the player's sprite, in tiles.
+    fn player_focus(&self) -> (f32, f32) {
+        let player = &self.entities[0];
+        (
+            player.position.x as f32 + player.animation.x.current + 0.5,
+            player.position.y as f32 + player.animation.y.current + 0.5,
+        )
+    }
+
+    /// Returns where the origin of the world is on the screen, when
+    /// the world is drawn in an area of the given size. The camera is
+    /// kept inside the level, so the void around it isn't shown,
+    /// unless the whole level fits in the area, in which case it's
+    /// centered.
+    fn camera_offset(&self, width: f32, height: f32) -> (f32, f32) {
+        let tile_size = self.view_tile_size(width, height);
+        let (mut focus_x, mut focus_y) = self.camera.unwrap_or_else(|| self.player_focus());
+        if !self.rooms.is_empty() {
+            let left = self.rooms.iter().map(|r| r.x).min().unwrap_or(0) as f32;
+            let top = self.rooms.iter().map(|r| r.y).min().unwrap_or(0) as f32;
+            let right = self.rooms.iter().map(|r| r.x + r.width).max().unwrap_or(0) as f32;
+            let bottom = self.rooms.iter().map(|r| r.y + r.height).max().unwrap_or(0) as f32;
+            focus_x = clamp_camera(focus_x, width / tile_size, left, right);
+            focus_y = clamp_camera(focus_y, height / tile_size, top, bottom);
+        }
+        (
+            width / 2.0 - focus_x * tile_size,
+            height / 2.0 - focus_y * tile_size,
+        )
+    }
+
     pub fn render(
         &self,
         ctx: &mut GraphicsContext,
-        _font: &Font,
+        font: &Font,
         tileset: &Spritesheet,
         show_debug_info: bool,
     ) {
-        let tile_size = 48.0;
         let drawable_width = ctx.width - crate::ui::UI_AREA_WIDTH;
         let drawable_height = ctx.height;
-        let offset = {
-            let player = &self.entities[0];
-            let focus_x = player.position.x as f32 + player.animation.x.current + 0.5;
-            let focus_y = player.position.y as f32 + player.animation.y.current + 0.5;
-            (
-                drawable_width / 2.0 - focus_x * tile_size,
-                drawable_height / 2.0 - focus_y * tile_size,
-            )
-        };
+        let tile_size = self.view_tile_size(drawable_width, drawable_height);
+        let offset = self.camera_offset(drawable_width, drawable_height);
 
         let player_room = self
             .rooms
@@ -435,12 +1524,16 @@ impl World {
         let mut draw_entity = |position: &Position,
                                sprite: &Sprite,
                                animation: &Animation,
+                               frame: TransitionFrame,
                                (ai_offset, flip): (i32, bool),
                                visibility_affected: bool,
                                z: f32| {
-            let x = (position.x as f32 + animation.x.current) * tile_size + offset.0;
-            let y = (position.y as f32 + animation.y.current) * tile_size + offset.1;
-            let mut sprite_data = sprite.0;
+            // Scaled sprites stay centered on their tile
+            let size = tile_size * frame.scale;
+            let margin = (tile_size - size) / 2.0;
+            let x = (position.x as f32 + animation.x.current) * tile_size + offset.0 + margin;
+            let y = (position.y as f32 + animation.y.current) * tile_size + offset.1 + margin;
+            let mut sprite_data = frame.sprite.unwrap_or(sprite.0);
             sprite_data.0 += sprite_data.2 * ai_offset;
             if flip {
                 sprite_data.0 += sprite_data.2;
@@ -466,27 +1559,37 @@ impl World {
 
             tileset
                 .draw(ctx)
-                .coordinates((x, y, tile_size, tile_size))
+                .coordinates((x, y, size, size))
                 .texture_coordinates(sprite_data)
                 .color((
                     brightness.0,
-                    brightness.1,
-                    brightness.2,
-                    animation.opacity.current,
+                    brightness.1 * (1.0 - animation.flash * 0.8),
+                    brightness.2 * (1.0 - animation.flash * 0.8),
+                    animation.opacity.current * frame.opacity,
                 ))
-                .rotation(animation.rotation.current, tile_size / 2.0, tile_size / 2.0)
+                .rotation(animation.rotation.current, size / 2.0, size / 2.0)
                 .z(z)
                 .finish();
         };
 
         let get_ai_state = |i: usize| {
             if let Some(ai) = &self.ais[i] {
-                ai.animation_state(i, &self.entities)
+                ai.animation_state(i, &self.entities, &self.context.options)
             } else {
                 (0, false)
             }
         };
 
+        // The entities spawned this round fade, grow or pop in when
+        // the animation gets to the step they were spawned in
+        let mut presences = vec![1.0; self.entities.len()];
+        for (step, transition) in &self.transitions {
+            if let EntityTransition::Spawn(i) = transition {
+                presences[*i] = self.spawn_presence(*step);
+            }
+        }
+        let spawn_frame = |i: usize| self.entities[i].animation.spawn.frame(presences[i]);
+
         let in_discovered_room = |(_, entity): &'_ (usize, &Entity)| {
             if show_debug_info {
                 true
@@ -501,14 +1604,15 @@ impl World {
             }
         };
 
-        // Draw the dead
+        // Draw the dead, and the traps lying on the floor
         for (i, position, sprite, animation, visibility_affected) in self
             .entities
             .iter()
             .enumerate()
             .skip(1)
             .filter(in_discovered_room)
-            .filter(|(_, e)| !e.is_alive() && !e.marked_for_death)
+            .filter(|(_, e)| (!e.is_alive() || e.trap.is_some()) && !e.marked_for_death)
+            .filter(|(_, e)| show_debug_info || !e.is_hidden())
             .map(|(i, e)| {
                 (
                     i,
@@ -523,6 +1627,7 @@ impl World {
                 position,
                 sprite,
                 animation,
+                spawn_frame(i),
                 get_ai_state(i),
                 visibility_affected,
                 layers::DEAD,
@@ -536,7 +1641,7 @@ impl World {
             .enumerate()
             .skip(1)
             .filter(in_discovered_room)
-            .filter(|(_, e)| e.is_alive() && !e.marked_for_death)
+            .filter(|(_, e)| e.is_alive() && e.trap.is_none() && !e.marked_for_death)
             .map(|(i, e)| {
                 (
                     i,
@@ -551,36 +1656,69 @@ impl World {
                 position,
                 sprite,
                 animation,
+                spawn_frame(i),
                 get_ai_state(i),
                 visibility_affected,
                 layers::ALIVE,
             );
         }
 
+        // Draw the entities that have been marked for death this
+        // round, until the animation has played their despawn out
+        for (step, ghost) in
+            self.transitions
+                .iter()
+                .filter_map(|(step, transition)| match transition {
+                    EntityTransition::Despawn(ghost) => Some((*step, ghost)),
+                    EntityTransition::Spawn(_) => None,
+                })
+        {
+            if step < self.animated_steps.start
+                || !in_discovered_room(&(0, ghost))
+                || (!show_debug_info && ghost.is_hidden())
+            {
+                continue;
+            }
+            let presence = 1.0 - self.spawn_presence(step);
+            let z = if !ghost.is_alive() || ghost.trap.is_some() {
+                layers::DEAD
+            } else {
+                layers::ALIVE
+            };
+            draw_entity(
+                &ghost.position,
+                &ghost.sprite,
+                &ghost.animation,
+                ghost.animation.despawn.frame(presence),
+                (0, false),
+                ghost.visibility_affected(),
+                z,
+            );
+        }
+
         // Draw player
         let player = &self.entities[0];
         draw_entity(
             &player.position,
             &player.sprite,
             &player.animation,
+            TransitionFrame::PRESENT,
             get_ai_state(0),
             player.visibility_affected,
             layers::ALIVE,
         );
 
         // Draw hearts
-        for (position, animation, health, visibility_affected) in self
+        for (i, e, health) in self
             .entities
             .iter()
             .enumerate()
             .filter(in_discovered_room)
             .filter(|(_, e)| e.is_alive() && !e.marked_for_death)
-            .filter_map(|(_, e)| {
-                e.health
-                    .as_ref()
-                    .map(|health| (&e.position, &e.animation, health, e.visibility_affected()))
-            })
+            .filter_map(|(i, e)| e.health.as_ref().map(|health| (i, e, health)))
         {
+            let (position, animation) = (&e.position, &e.animation);
+            let visibility_affected = e.visibility_affected();
             let &Position { x, y } = position;
             if visibility_affected && !player_room.contains(x, y) {
                 continue;
@@ -589,12 +1727,163 @@ impl World {
                 (x as f32 + animation.x.current) * tile_size + offset.0,
                 (y as f32 + animation.y.current) * tile_size + offset.1,
             );
-            let dark = (0.2, 0.5, 0.8, 0.3 * animation.opacity.current);
-            let light = (0.7, 0.05, 0.05, 1.0 * animation.opacity.current);
+            let opacity = animation.opacity.current * presences[i];
+            let dark = (0.2, 0.5, 0.8, 0.3 * opacity);
+            let light = (0.7, 0.05, 0.05, 1.0 * opacity);
             let (current, max) = (health.current, health.max);
             draw_hearts(ctx, tileset, pos, tile_size, dark, max, max);
             draw_hearts(ctx, tileset, pos, tile_size, light, current, max);
+            if let Some(status_effects) = &e.status_effects {
+                draw_status_icons(ctx, tileset, pos, tile_size, opacity, status_effects, max);
+            }
+        }
+
+        // Draw the damage and healing numbers of the step being
+        // animated, floating up and fading out
+        let font_size = tile_size * 0.4;
+        let progress = self.animation_progress;
+        let mut numbered_entities = Vec::new();
+        for &(_, i, event) in self
+            .combat_events
+            .iter()
+            .filter(|(step, _, _)| self.animated_steps.contains(step))
+        {
+            let (text, color) = match event {
+                CombatEvent::Damage(amount) => (format!("-{}", amount), DAMAGE_COLOR),
+                CombatEvent::Poison(amount) => (format!("-{}", amount), POISON_COLOR),
+                CombatEvent::Heal(amount) => (format!("+{}", amount), HEAL_COLOR),
+                CombatEvent::Attack(_, _) => continue,
+            };
+            let (position, animation) = (&self.entities[i].position, &self.entities[i].animation);
+            if !show_debug_info && !player_room.contains(position.x, position.y) {
+                continue;
+            }
+            // Multiple numbers on the same entity stack up
+            let stacked = numbered_entities.iter().filter(|&&j| j == i).count();
+            numbered_entities.push(i);
+            let rise = FLOATING_NUMBER_RISE * progress;
+            let x = (position.x as f32 + animation.x.current) * tile_size + offset.0;
+            let y = (position.y as f32 + animation.y.current - rise) * tile_size + offset.1
+                - stacked as f32 * font_size;
+            font.draw(ctx, &text, x, y, font_size)
+                .color((color.0, color.1, color.2, 1.0 - progress))
+                .max_width(tile_size)
+                .alignment(Alignment::Center)
+                .z(layers::HEARTS)
+                .finish();
+        }
+    }
+
+    /// Draws a map of the discovered rooms, their doors, the stairs if
+    /// they've been seen, and the player, scaled to fit the `area`.
+    pub fn render_map(
+        &self,
+        ctx: &mut GraphicsContext,
+        tileset: &Spritesheet,
+        (area_x, area_y, area_width, area_height): (f32, f32, f32, f32),
+    ) {
+        let rooms = &self.discovered_rooms;
+        let min_x = rooms.iter().map(|room| room.x).min().unwrap_or(0);
+        let min_y = rooms.iter().map(|room| room.y).min().unwrap_or(0);
+        let max_x = rooms
+            .iter()
+            .map(|room| room.x + room.width)
+            .max()
+            .unwrap_or(1);
+        let max_y = rooms
+            .iter()
+            .map(|room| room.y + room.height)
+            .max()
+            .unwrap_or(1);
+        let (width, height) = ((max_x - min_x) as f32, (max_y - min_y) as f32);
+        let tile_size = (area_width / width).min(area_height / height);
+        let offset_x = area_x + (area_width - width * tile_size) / 2.0 - min_x as f32 * tile_size;
+        let offset_y = area_y + (area_height - height * tile_size) / 2.0 - min_y as f32 * tile_size;
+
+        let draw_rect = |ctx: &mut GraphicsContext, x: i32, y: i32, w: i32, h: i32, color, z| {
+            tileset
+                .draw(ctx)
+                .coordinates((
+                    offset_x + x as f32 * tile_size,
+                    offset_y + y as f32 * tile_size,
+                    w as f32 * tile_size,
+                    h as f32 * tile_size,
+                ))
+                .color(color)
+                .z(z)
+                .finish();
+        };
+        let is_discovered =
+            |&Position { x, y }: &Position| rooms.iter().any(|room| room.contains(x, y));
+        let is_here = |e: &&Entity, position: &Position| {
+            e.position.x == position.x && e.position.y == position.y && !e.marked_for_death
+        };
+
+        // The floors are drawn over the walls, so that the walls
+        // shared between rooms don't cover their neighbors.
+        for room in rooms {
+            let (x, y, w, h) = (room.x, room.y, room.width, room.height);
+            draw_rect(ctx, x, y, w, h, MAP_WALL_COLOR, layers::UI_MAP);
+            draw_rect(
+                ctx,
+                x + 1,
+                y + 1,
+                w - 2,
+                h - 2,
+                MAP_FLOOR_COLOR,
+                layers::UI_MAP_FLOOR,
+            );
         }
+
+        for doorway in self.doorways.iter().filter(|p| is_discovered(p)) {
+            let mut entities_here = self.entities.iter().filter(|e| is_here(e, doorway));
+            let color = match entities_here.find(|e| e.door || e.hidden_door.is_some()) {
+                Some(entity) if entity.door => MAP_DOOR_COLOR,
+                Some(_) => continue,
+                None => MAP_FLOOR_COLOR,
+            };
+            draw_rect(
+                ctx,
+                doorway.x,
+                doorway.y,
+                1,
+                1,
+                color,
+                layers::UI_MAP_MARKERS,
+            );
+        }
+
+        for stairs in self
+            .entities
+            .iter()
+            .filter(|e| e.next_level && !e.marked_for_death && is_discovered(&e.position))
+        {
+            let Position { x, y } = stairs.position;
+            draw_rect(ctx, x, y, 1, 1, MAP_STAIRS_COLOR, layers::UI_MAP_MARKERS);
+        }
+
+        let Position { x, y } = self.entities[0].position;
+        draw_rect(ctx, x, y, 1, 1, MAP_PLAYER_COLOR, layers::UI_MAP_MARKERS);
+    }
+}
+
+/// Keeps the camera centered on `focus` within `start..end`, so that
+/// a view `view_size` wide doesn't go past either end. If the view is
+/// wider than the range, it's centered on the range.
+fn clamp_camera(focus: f32, view_size: f32, start: f32, end: f32) -> f32 {
+    if end - start <= view_size {
+        (start + end) / 2.0
+    } else {
+        focus.clamp(start + view_size / 2.0, end - view_size / 2.0)
+    }
+}
+
+/// The opacity and rotation of an entity that isn't being animated.
+fn resting_animation(alive: bool) -> (f32, f32) {
+    if alive {
+        (1.0, 0.0)
+    } else {
+        (0.2, std::f32::consts::PI * 0.4)
     }
 }
 
@@ -607,133 +1896,299 @@ pub fn split_entities(
     (&mut separated[0], head.iter_mut().chain(tail.iter_mut()))
 }
 
-fn pickup(position: &Position, health: &mut Health, inventory: &mut Inventory, others: EntityIter) {
+/// Picks up the item under the player. If `replaced_slot` is set, the
+/// item in that slot is dropped in its place, otherwise the item is
+/// only picked up if there's room for it.
+fn pickup(
+    player: &mut Entity,
+    others: EntityIter,
+    replaced_slot: Option<usize>,
+    context: &mut TurnContext,
+) {
+    let position = &player.position;
     if let Some(pickup) = others
-        .filter(|e| e.position == *position && e.drop.is_some())
+        .filter(|e| e.position == *position && e.drop.is_some() && e.price.is_none())
         .nth(0)
     {
+        let original_drop = pickup.drop;
+        let inventory = player.inventory.as_mut().unwrap();
         if let Some(item) = pickup.drop {
             if item == Item::Apple {
+                let health = player.health.as_ref().unwrap();
                 if health.current < health.max {
                     pickup.drop = None;
                     pickup.marked_for_death = true;
-                    health.current = health.max;
+                    let missing_health = health.max - health.current;
+                    player.heal(missing_health);
                 } else {
                     // TODO: Notify: "max health already"
                 }
-            } else if let Some(replacing_item) = inventory.add_item(item) {
-                pickup.drop = Some(replacing_item);
-                pickup.sprite = replacing_item.sprite();
-            } else {
+            } else if let Item::Gold(amount) = item {
+                pickup.drop = None;
+                pickup.marked_for_death = true;
+                inventory.gold += amount;
+            } else if let Item::Consumable(consumable) = item {
+                if inventory.add_consumable(consumable) {
+                    pickup.drop = None;
+                    pickup.marked_for_death = true;
+                }
+            } else if let Some(slot) = replaced_slot {
+                if let Some(replaced_item) = inventory.replace_item(slot, item) {
+                    pickup.drop = Some(replaced_item);
+                    pickup.sprite = replaced_item.sprite();
+                } else {
+                    pickup.drop = None;
+                    pickup.marked_for_death = true;
+                }
+            } else if inventory.add_item(item) {
                 pickup.drop = None;
                 pickup.marked_for_death = true;
             }
         }
+        if pickup.drop != original_drop {
+            context.sounds.push(Sound::Pickup);
+        }
     }
 }
 
+/// Returns the tiles on the line from (`x0`, `y0`) to (`x1`, `y1`),
+/// both ends included.
+pub fn line(x0: i32, y0: i32, x1: i32, y1: i32) -> Vec<(i32, i32)> {
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+    let (mut x, mut y) = (x0, y0);
+    let mut error = dx + dy;
+    let mut tiles = vec![(x, y)];
+    while x != x1 || y != y1 {
+        let e2 = 2 * error;
+        if e2 >= dy {
+            error += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            error += dx;
+            y += sy;
+        }
+        tiles.push((x, y));
+    }
+    tiles
+}
+
+/// Returns true if `other` is a wall or a door on one of the corners
+/// that a diagonal step by (`xd`, `yd`) from `position` would squeeze
+/// past. Such steps aren't allowed, which also keeps everyone from
+/// moving diagonally through doorways.
+pub fn cuts_corner(position: &Position, xd: i32, yd: i32, other: &Entity) -> bool {
+    let &Position { x, y } = &other.position;
+    xd != 0
+        && yd != 0
+        && other.denies_movement
+        && other.health.is_none()
+        && other.is_alive()
+        && ((x == position.x + xd && y == position.y) || (x == position.x && y == position.y + yd))
+}
+
 pub fn move_entity(position: &mut Position, others: EntityIter, xd: i32, yd: i32) -> bool {
     let (new_x, new_y) = (position.x + xd, position.y + yd);
     for other in others.filter(|e| e.denies_movement && e.is_alive()) {
         if new_x == other.position.x && new_y == other.position.y {
             return false;
         }
+        if cuts_corner(position, xd, yd, other) {
+            return false;
+        }
     }
     position.x = new_x;
     position.y = new_y;
     true
 }
 
-// TODO: Animate attacks
 pub fn attack_direction(
-    position: &Position,
-    damage: &Damage,
-    health: &mut Option<Health>,
-    inventory: &Option<Inventory>,
+    attacker: &mut Entity,
     others: EntityIter,
     xd: i32,
     yd: i32,
+    context: &mut TurnContext,
 ) {
-    let has_item = |item: Item| inventory.iter().any(|inv| inv.has_item(item));
+    let attacker_items = carried_items(attacker);
+    attack_direction_with_items(attacker, others, xd, yd, &attacker_items, context);
+}
 
-    let (target_x, target_y) = (position.x + xd, position.y + yd);
+/// Like attack_direction, but the item effects are those of
+/// `attacker_items` instead of the attacker's inventory. Thrown items
+/// hit with their own effects this way.
+fn attack_direction_with_items<'a>(
+    attacker: &mut Entity,
+    others: impl Iterator<Item = &'a mut Entity>,
+    xd: i32,
+    yd: i32,
+    attacker_items: &[Item],
+    context: &mut TurnContext,
+) {
+    let knockback = attacker.knockback
+        + attacker_items
+            .iter()
+            .map(|item| item.effect().knockback())
+            .sum::<i32>();
+
+    if xd != 0 || yd != 0 {
+        attacker.animation.events.push(CombatEvent::Attack(xd, yd));
+    }
+
+    let (target_x, target_y) = (attacker.position.x + xd, attacker.position.y + yd);
     let mut damage_dealt = 0;
-    for target in others
-        .filter(|e| e.position.x == target_x && e.position.y == target_y && e.health.is_some())
-    {
+    for target in others.filter(|e| {
+        e.position.x == target_x && e.position.y == target_y && e.health.is_some() && e.is_alive()
+    }) {
+        let roll = context.roll_attack(attacker, target);
+        let explanation = match &roll.explanation {
+            Some(explanation) => format!(" ({})", explanation),
+            None => String::new(),
+        };
+        if !roll.hit {
+            context.log.add(format!(
+                "{} vs {}: miss{}",
+                attacker.name, target.name, explanation
+            ));
+            continue;
+        }
+
+        let damage_taken = calculate_damage(attacker, target, roll.critical);
+        let critical = if roll.critical { "critical " } else { "" };
+        context.log.add(format!(
+            "{} vs {}: {}hit for {}{}",
+            attacker.name, target.name, critical, damage_taken, explanation
+        ));
         if let Some(target_health) = &mut target.health {
-            let damage_taken =
-                calculate_damage(damage, inventory, target_health, &target.inventory);
             let previous_target_health = target_health.current;
             target_health.current = (target_health.current - damage_taken).max(0);
-            damage_dealt += previous_target_health - target_health.current;
-        }
-
-        if let Some(target_status_effects) = &mut target.status_effects {
-            if has_item(Item::Dagger) {
-                let poison_duration = 6;
-                let poisoned_already =
-                    target_status_effects
-                        .iter_mut()
-                        .any(|status_effect| match status_effect {
-                            StatusEffect::Poison { duration, .. } => {
-                                *duration = poison_duration;
-                                true
-                            }
-                            _ => false,
-                        });
-                if !poisoned_already {
-                    target_status_effects.push(StatusEffect::Poison {
-                        stacks: 1,
-                        duration: poison_duration,
-                    });
-                }
+            let lost_health = previous_target_health - target_health.current;
+            if lost_health > 0 {
+                target
+                    .animation
+                    .events
+                    .push(CombatEvent::Damage(lost_health));
             }
+            damage_dealt += lost_health;
+        }
 
-            if has_item(Item::Hammer) {
-                if !target_status_effects
-                    .iter()
-                    .any(|eff| *eff == StatusEffect::Stun || *eff == StatusEffect::StunImmunity)
-                {
-                    target_status_effects.push(StatusEffect::Stun);
-                }
-            }
+        for item in attacker_items {
+            item.effect().on_hit(target);
+        }
+        if let Some(effect) = attacker.inflicts {
+            target.apply_status_effect(effect);
+        }
+        for item in carried_items(target) {
+            item.effect().on_damaged(target, damage_taken);
+        }
+        if knockback > 0 && (xd != 0 || yd != 0) && target.is_alive() {
+            context.knockbacks.push(Knockback {
+                x: target.position.x,
+                y: target.position.y,
+                xd,
+                yd,
+                distance: knockback,
+            });
         }
     }
 
-    if has_item(Item::VampireTeeth) {
-        if let Some(health) = health {
-            health.current = (health.current + damage_dealt / 2).min(health.max);
-        }
+    for item in attacker_items {
+        item.effect().on_damage_dealt(attacker, damage_dealt);
     }
 }
 
-fn calculate_damage(
-    attacker_damage: &Damage,
-    attacker_inventory: &Option<Inventory>,
-    defender_health: &Health,
-    defender_inventory: &Option<Inventory>,
-) -> i32 {
-    let mut damage = attacker_damage.0;
+/// Copies the entity's items out, so their effects can modify the
+/// entity.
+fn carried_items(entity: &Entity) -> Vec<Item> {
+    match &entity.inventory {
+        Some(inventory) => inventory.items.clone(),
+        None => Vec::new(),
+    }
+}
 
+/// Calculates how much damage the attacker deals to the defender in
+/// one hit. The steps, in order:
+///
+/// 1. The attacker's base Damage.
+/// 2. Attacker bonuses: the attacker's status effects'
+///    StatusBehavior::modify_damage (Strength, Weakness), then the
+///    attacker's items' ItemEffect::modify_damage.
+/// 3. Critical hits double the damage so far.
+/// 4. The defender's flat defense is subtracted.
+/// 5. The defender's percentage defense blocks that share of the
+///    rest, rounding in the defender's favor.
+/// 6. Defense never brings a hit below 1 damage, so that every
+///    attack that dealt damage after step 3 still deals some.
+/// 7. The attacker's items' ItemEffect::on_attack, for effects that
+///    depend on the defender, like the Scythe's execution, which
+///    armor doesn't soften.
+///
+/// Steps 1 and 2 are Entity::effective_damage. The defense is the
+/// defender's natural Defense combined with the defense of their
+/// items, see Entity::effective_defense.
+fn calculate_damage(attacker: &Entity, defender: &Entity, critical: bool) -> i32 {
     // Apply attacker bonuses
-    if let Some(inv) = attacker_inventory {
-        damage = inv.damage_after_items(damage);
-        if inv.has_item(Item::Scythe) && defender_health.current <= defender_health.max / 2 {
-            damage = defender_health.current;
-        }
+    let mut damage = attacker.effective_damage();
+    if critical {
+        damage *= 2;
     }
 
     // Apply defender bonuses
-    if let Some(inv) = defender_inventory {
-        if inv.has_item(Item::Shield) && damage > 1 {
-            damage /= 2;
+    if damage > 0 {
+        let defense = defender.effective_defense();
+        let after_flat = damage - defense.flat;
+        let after_percent = after_flat * (100 - defense.percent) / 100;
+        damage = after_percent.max(1);
+    }
+
+    // Apply the attack's effects last, so that e.g. the Scythe's
+    // execution isn't softened by armor
+    if let Some(inv) = &attacker.inventory {
+        for item in &inv.items {
+            damage = item.effect().on_attack(damage, defender);
         }
     }
 
     damage
 }
 
+/// Draws the status effects' icons in a row above the hearts drawn by
+/// draw_hearts.
+fn draw_status_icons(
+    ctx: &mut GraphicsContext,
+    tileset: &Spritesheet,
+    (x, y): (f32, f32),
+    tile_size: f32,
+    opacity: f32,
+    status_effects: &[StatusEffect],
+    heart_quarters_max: i32,
+) {
+    let hearts_max = (heart_quarters_max as f32 / 4.0).ceil() as i32;
+    let heart_rows = (hearts_max as f32 / 3.0).ceil();
+    let heart_size = tile_size * 6.0 / 16.0;
+    let icon_size = tile_size * 6.0 / 16.0;
+    let icons: Vec<_> = status_effects
+        .iter()
+        .filter_map(|effect| effect.kind.behavior().icon())
+        .collect();
+    let row_width = icon_size * icons.len() as f32;
+    for (i, icon) in icons.into_iter().enumerate() {
+        let coords = (
+            x + tile_size / 2.0 - row_width / 2.0 + icon_size * i as f32,
+            y - heart_size * heart_rows - icon_size,
+            icon_size,
+            icon_size,
+        );
+        tileset
+            .draw(ctx)
+            .coordinates(coords)
+            .texture_coordinates(icon)
+            .z(layers::HEARTS)
+            .color((1.0, 1.0, 1.0, opacity))
+            .finish();
+    }
+}
+
 fn draw_hearts(
     ctx: &mut GraphicsContext,
     tileset: &Spritesheet,
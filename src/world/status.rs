@@ -0,0 +1,329 @@
+//! Status effects: what they do when applied, every turn and when
+//! they run out, and how they stack. Each kind is a type implementing
+//! StatusBehavior, mapped to by StatusKind::behavior.
+use super::entity::{CombatEvent, Entity};
+use crate::sprites::{self, SpriteData};
+
+pub const STRENGTH_BONUS: i32 = 2;
+pub const WEAKNESS_PENALTY: i32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatusKind {
+    Stun,
+    /// Given for a turn after a stun wears off, so stuns can't be
+    /// chained.
+    StunImmunity,
+    Poison,
+    /// Everyone else only gets to act every other turn.
+    Haste,
+    Strength,
+    /// Makes monsters run away from the player.
+    Fear,
+    Burn,
+    Bleed,
+    /// Only acts every other turn.
+    Slow,
+    Regeneration,
+    Weakness,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    /// Turns left. The effect wears off when this hits 0.
+    pub duration: i32,
+    pub stacks: i32,
+}
+
+impl StatusEffect {
+    pub const fn new(kind: StatusKind, duration: i32) -> StatusEffect {
+        StatusEffect {
+            kind,
+            duration,
+            stacks: 1,
+        }
+    }
+}
+
+/// What happens when an effect is applied to an entity that already
+/// has an effect of the same kind.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stacking {
+    /// The longer of the two durations is kept.
+    Refresh,
+    /// A stack is added, up to `max`, and the duration is refreshed.
+    Stack { max: i32 },
+    /// The durations are added together.
+    Extend,
+}
+
+pub trait StatusBehavior {
+    fn name(&self) -> &'static str;
+
+    /// The icon drawn next to the hearts.
+    fn icon(&self) -> Option<SpriteData> {
+        None
+    }
+
+    fn stacking(&self) -> Stacking {
+        Stacking::Refresh
+    }
+
+    /// The kinds of effects that can't be applied while this one is.
+    fn immunities(&self) -> &'static [StatusKind] {
+        &[]
+    }
+
+    /// Whether the entity has to skip its turn.
+    fn prevents_action(&self, _effect: &StatusEffect) -> bool {
+        false
+    }
+
+    /// Changes the damage the entity deals, before its items' effects.
+    fn modify_damage(&self, damage: i32, _effect: &StatusEffect) -> i32 {
+        damage
+    }
+
+    /// Called when the effect is first applied, not when it's
+    /// refreshed or stacked.
+    fn on_apply(&self, _entity: &mut Entity, _effect: &StatusEffect) {}
+
+    /// Called at the end of each of the entity's turns, before the
+    /// duration goes down.
+    fn on_tick(&self, _entity: &mut Entity, _effect: &StatusEffect) {}
+
+    /// Called when the duration runs out.
+    fn on_expire(&self, _entity: &mut Entity, _effect: &StatusEffect) {}
+}
+
+impl StatusKind {
+    pub fn behavior(&self) -> &dyn StatusBehavior {
+        match self {
+            StatusKind::Stun => &Stun,
+            StatusKind::StunImmunity => &StunImmunity,
+            StatusKind::Poison => &Poison,
+            StatusKind::Haste => &Haste,
+            StatusKind::Strength => &Strength,
+            StatusKind::Fear => &Fear,
+            StatusKind::Burn => &Burn,
+            StatusKind::Bleed => &Bleed,
+            StatusKind::Slow => &Slow,
+            StatusKind::Regeneration => &Regeneration,
+            StatusKind::Weakness => &Weakness,
+        }
+    }
+}
+
+/// Deals damage straight to the entity's health, past any defense.
+pub fn take_damage(entity: &mut Entity, damage: i32) {
+    take_damage_shown_as(entity, damage, CombatEvent::Damage);
+}
+
+/// Like take_damage, but the damage is shown in the animation as the
+/// given kind of event.
+fn take_damage_shown_as(entity: &mut Entity, damage: i32, event: fn(i32) -> CombatEvent) {
+    if let Some(health) = &mut entity.health {
+        let previous = health.current;
+        health.current = (health.current - damage).max(0);
+        let dealt = previous - health.current;
+        if dealt > 0 {
+            entity.animation.events.push(event(dealt));
+        }
+    }
+}
+
+struct Stun;
+
+impl StatusBehavior for Stun {
+    fn name(&self) -> &'static str {
+        "Stunned"
+    }
+
+    fn icon(&self) -> Option<SpriteData> {
+        Some(sprites::ICON_STUN)
+    }
+
+    fn prevents_action(&self, _effect: &StatusEffect) -> bool {
+        true
+    }
+
+    fn on_expire(&self, entity: &mut Entity, _effect: &StatusEffect) {
+        entity.apply_status_effect(StatusEffect::new(StatusKind::StunImmunity, 1));
+    }
+}
+
+struct StunImmunity;
+
+impl StatusBehavior for StunImmunity {
+    fn name(&self) -> &'static str {
+        "Dazed"
+    }
+
+    fn immunities(&self) -> &'static [StatusKind] {
+        &[StatusKind::Stun]
+    }
+}
+
+struct Poison;
+
+impl StatusBehavior for Poison {
+    fn name(&self) -> &'static str {
+        "Poisoned"
+    }
+
+    fn icon(&self) -> Option<SpriteData> {
+        Some(sprites::ICON_POISON)
+    }
+
+    fn on_tick(&self, entity: &mut Entity, effect: &StatusEffect) {
+        take_damage_shown_as(entity, effect.stacks, CombatEvent::Poison);
+    }
+}
+
+struct Haste;
+
+impl StatusBehavior for Haste {
+    fn name(&self) -> &'static str {
+        "Hasted"
+    }
+
+    fn icon(&self) -> Option<SpriteData> {
+        Some(sprites::ICON_HASTE)
+    }
+}
+
+struct Strength;
+
+impl StatusBehavior for Strength {
+    fn name(&self) -> &'static str {
+        "Strong"
+    }
+
+    fn icon(&self) -> Option<SpriteData> {
+        Some(sprites::ICON_STRENGTH)
+    }
+
+    fn immunities(&self) -> &'static [StatusKind] {
+        &[StatusKind::Weakness]
+    }
+
+    fn modify_damage(&self, damage: i32, _effect: &StatusEffect) -> i32 {
+        damage + STRENGTH_BONUS
+    }
+}
+
+struct Fear;
+
+impl StatusBehavior for Fear {
+    fn name(&self) -> &'static str {
+        "Afraid"
+    }
+
+    fn icon(&self) -> Option<SpriteData> {
+        Some(sprites::ICON_FEAR)
+    }
+}
+
+struct Burn;
+
+impl StatusBehavior for Burn {
+    fn name(&self) -> &'static str {
+        "Burning"
+    }
+
+    fn icon(&self) -> Option<SpriteData> {
+        Some(sprites::ICON_BURN)
+    }
+
+    /// The flames make short work of any cobwebs stuck on the entity.
+    fn on_apply(&self, entity: &mut Entity, _effect: &StatusEffect) {
+        entity.remove_status_effect(StatusKind::Slow);
+    }
+
+    fn on_tick(&self, entity: &mut Entity, _effect: &StatusEffect) {
+        take_damage(entity, 1);
+    }
+}
+
+struct Bleed;
+
+impl StatusBehavior for Bleed {
+    fn name(&self) -> &'static str {
+        "Bleeding"
+    }
+
+    fn icon(&self) -> Option<SpriteData> {
+        Some(sprites::ICON_BLEED)
+    }
+
+    fn stacking(&self) -> Stacking {
+        Stacking::Stack { max: 3 }
+    }
+
+    fn on_tick(&self, entity: &mut Entity, effect: &StatusEffect) {
+        take_damage(entity, effect.stacks);
+    }
+}
+
+struct Slow;
+
+impl StatusBehavior for Slow {
+    fn name(&self) -> &'static str {
+        "Slowed"
+    }
+
+    fn icon(&self) -> Option<SpriteData> {
+        Some(sprites::ICON_SLOW)
+    }
+
+    fn prevents_action(&self, effect: &StatusEffect) -> bool {
+        effect.duration % 2 == 0
+    }
+}
+
+struct Regeneration;
+
+impl StatusBehavior for Regeneration {
+    fn name(&self) -> &'static str {
+        "Regenerating"
+    }
+
+    fn icon(&self) -> Option<SpriteData> {
+        Some(sprites::ICON_REGENERATION)
+    }
+
+    fn stacking(&self) -> Stacking {
+        Stacking::Extend
+    }
+
+    fn immunities(&self) -> &'static [StatusKind] {
+        &[StatusKind::Poison, StatusKind::Bleed]
+    }
+
+    fn on_apply(&self, entity: &mut Entity, _effect: &StatusEffect) {
+        entity.remove_status_effect(StatusKind::Poison);
+        entity.remove_status_effect(StatusKind::Bleed);
+    }
+
+    fn on_tick(&self, entity: &mut Entity, _effect: &StatusEffect) {
+        if entity.is_alive() {
+            entity.heal(1);
+        }
+    }
+}
+
+struct Weakness;
+
+impl StatusBehavior for Weakness {
+    fn name(&self) -> &'static str {
+        "Weakened"
+    }
+
+    fn icon(&self) -> Option<SpriteData> {
+        Some(sprites::ICON_WEAKNESS)
+    }
+
+    fn modify_damage(&self, damage: i32, _effect: &StatusEffect) -> i32 {
+        (damage - WEAKNESS_PENALTY).max(0)
+    }
+}